@@ -0,0 +1,6 @@
+//! Builds the C interface of the `capi` feature as a shared library, so the `chromaprint` crate
+//! itself stays an rlib which also builds without std.
+
+extern crate chromaprint_core;
+
+pub use chromaprint_core::capi::*;
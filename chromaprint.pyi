@@ -0,0 +1,21 @@
+from typing import Optional, Sequence, Tuple
+
+import numpy as np
+import numpy.typing as npt
+
+class Fingerprinter:
+    def __init__(self, sample_rate: int, channels: int, algorithm: int = 1) -> None: ...
+    def feed(self, samples: npt.NDArray[np.int16 | np.float32 | np.float64 | np.int32 | np.uint8]) -> None: ...
+    def finish(self) -> None: ...
+    def fingerprint(self) -> npt.NDArray[np.uint32]: ...
+    @property
+    def algorithm(self) -> int: ...
+    def compress(self) -> bytes: ...
+    def encode(self) -> str: ...
+
+def compress(fingerprint: Sequence[int], algorithm: int = 1) -> bytes: ...
+def decompress(compressed: bytes) -> Tuple[npt.NDArray[np.uint32], int]: ...
+def encode(compressed: bytes) -> str: ...
+def decode(encoded: str) -> bytes: ...
+def compare(a: Sequence[int], b: Sequence[int], offset: int = 0) -> Optional[float]: ...
+def best_offset(a: Sequence[int], b: Sequence[int], max_offset: Optional[int] = None) -> Optional[int]: ...
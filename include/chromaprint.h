@@ -0,0 +1,151 @@
+#ifndef CHROMAPRINT_CHROMAPRINT_H_
+#define CHROMAPRINT_CHROMAPRINT_H_
+
+/* Generated by cbindgen from src/capi.rs. Don't edit by hand. */
+
+#include <stdint.h>
+
+#define CHROMAPRINT_ALGORITHM_TEST1 0
+
+#define CHROMAPRINT_ALGORITHM_TEST2 1
+
+#define CHROMAPRINT_ALGORITHM_TEST3 2
+
+#define CHROMAPRINT_ALGORITHM_TEST4 3
+
+#define CHROMAPRINT_ALGORITHM_TEST5 4
+
+#define CHROMAPRINT_ALGORITHM_DEFAULT CHROMAPRINT_ALGORITHM_TEST2
+
+/**
+ * A fingerprinter which is reset for each stream by `chromaprint_start`.
+ */
+typedef struct ChromaprintContext ChromaprintContext;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The version of this library.
+ */
+const char *chromaprint_get_version(void);
+
+/**
+ * Allocates a context which fingerprints with `algorithm`.
+ *
+ * # Returns
+ * A null pointer when `algorithm` isn't known.
+ */
+ChromaprintContext *chromaprint_new(int algorithm);
+
+/**
+ * Releases a context allocated by `chromaprint_new`.
+ *
+ * # Safety
+ * `ctx` must be null or a context returned by `chromaprint_new` which hasn't been freed.
+ */
+void chromaprint_free(ChromaprintContext *ctx);
+
+/**
+ * # Safety
+ * `ctx` must be null or a live context.
+ */
+int chromaprint_get_algorithm(ChromaprintContext *ctx);
+
+/**
+ * Starts fingerprinting a new stream of interleaved audio, discarding the previous
+ * fingerprint.
+ *
+ * # Safety
+ * `ctx` must be null or a live context.
+ */
+int chromaprint_start(ChromaprintContext *ctx, int sample_rate, int num_channels);
+
+/**
+ * Feeds `size` interleaved samples, counting the samples of every channel.
+ *
+ * # Safety
+ * `ctx` must be null or a live context and `data` must point to `size` samples.
+ */
+int chromaprint_feed(ChromaprintContext *ctx, const int16_t *data, int size);
+
+/**
+ * Processes any buffered audio once the whole stream has been fed.
+ *
+ * # Safety
+ * `ctx` must be null or a live context.
+ */
+int chromaprint_finish(ChromaprintContext *ctx);
+
+/**
+ * Returns the compressed fingerprint encoded in base64 as a null terminated string.
+ *
+ * # Safety
+ * `ctx` must be null or a live context and `fingerprint` must be valid for writes.
+ */
+int chromaprint_get_fingerprint(ChromaprintContext *ctx, char **fingerprint);
+
+/**
+ * Returns the raw sub-fingerprints computed so far.
+ *
+ * # Safety
+ * `ctx` must be null or a live context and `fingerprint` and `size` must be valid for writes.
+ */
+int chromaprint_get_raw_fingerprint(ChromaprintContext *ctx, uint32_t **fingerprint, int *size);
+
+/**
+ * # Safety
+ * `ctx` must be null or a live context and `size` must be valid for writes.
+ */
+int chromaprint_get_raw_fingerprint_size(ChromaprintContext *ctx, int *size);
+
+/**
+ * Computes `chromaprint_hash_fingerprint` for the fingerprint computed so far.
+ *
+ * # Safety
+ * `ctx` must be null or a live context and `hash` must be valid for writes.
+ */
+int chromaprint_get_fingerprint_hash(ChromaprintContext *ctx, uint32_t *hash);
+
+/**
+ * Compresses raw sub-fingerprints computed with `algorithm`, encoding them in base64 when
+ * `base64` is non-zero. The result is null terminated, and its length excluding the terminator
+ * is written to `encoded_size`.
+ *
+ * # Safety
+ * `fp` must point to `size` items and the out parameters must be valid for writes.
+ */
+int chromaprint_encode_fingerprint(const uint32_t *fp, int size, int algorithm, char **encoded_fp, int *encoded_size, int base64);
+
+/**
+ * Reverses `chromaprint_encode_fingerprint`, writing the id of the algorithm to `algorithm`.
+ *
+ * # Safety
+ * `encoded_fp` must point to `encoded_size` bytes and the out parameters must be valid for
+ * writes.
+ */
+int chromaprint_decode_fingerprint(const char *encoded_fp, int encoded_size, uint32_t **fp, int *size, int *algorithm, int base64);
+
+/**
+ * Hashes raw sub-fingerprints into 32 bits, so that similar fingerprints have hashes which
+ * differ in few bits.
+ *
+ * # Safety
+ * `fp` must point to `size` items and `hash` must be valid for writes.
+ */
+int chromaprint_hash_fingerprint(const uint32_t *fp, int size, uint32_t *hash);
+
+/**
+ * Frees memory returned by the other functions.
+ *
+ * # Safety
+ * `ptr` must be null or memory returned by this library which hasn't been freed.
+ */
+void chromaprint_dealloc(void *ptr);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHROMAPRINT_CHROMAPRINT_H_ */
@@ -0,0 +1,878 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use ureq::Agent;
+
+use fingerprinter::CompressedFingerprint;
+
+/// The address of the AcoustID web service.
+const DEFAULT_BASE_URL: &str = "https://api.acoustid.org/v2";
+
+/// AcoustID asks clients not to make more than 3 requests per second.
+const DEFAULT_REQUESTS_PER_SECOND: f64 = 3.0;
+
+/// The most fingerprints sent in one submission request.
+const MAX_SUBMISSION_BATCH: usize = 50;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest delay before retrying a request.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// A failed request to the AcoustID web service.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AcoustIdError {
+    /// The request couldn't be sent or the response couldn't be read.
+    Transport(String),
+
+    /// The server responded with an HTTP error status and no details of the error.
+    Http(u16),
+
+    /// The web service rejected the request.
+    Service { code: i64, message: String },
+
+    /// The response wasn't in the format the web service documents.
+    InvalidResponse(String),
+
+    /// A batch of submissions failed after the service accepted the batches before it. Only the
+    /// submissions after the ones in `statuses` need to be sent again.
+    PartlySubmitted {
+        statuses: Vec<SubmissionStatus>,
+        error: Box<AcoustIdError>,
+    },
+}
+
+impl fmt::Display for AcoustIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AcoustIdError::Transport(ref message) => {
+                write!(f, "request to acoustid failed: {}", message)
+            }
+            AcoustIdError::Http(status) => write!(f, "acoustid responded with status {}", status),
+            AcoustIdError::Service { code, ref message } => {
+                write!(f, "acoustid error {}: {}", code, message)
+            }
+            AcoustIdError::InvalidResponse(ref message) => {
+                write!(f, "invalid response from acoustid: {}", message)
+            }
+            AcoustIdError::PartlySubmitted {
+                ref statuses,
+                ref error,
+            } => write!(
+                f,
+                "{} (after acoustid accepted {} submissions)",
+                error,
+                statuses.len()
+            ),
+        }
+    }
+}
+
+impl Error for AcoustIdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            AcoustIdError::PartlySubmitted { ref error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+/// An AcoustID track whose fingerprint matches the one looked up.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct LookupResult {
+    /// The AcoustID track id.
+    pub id: String,
+
+    /// How closely the fingerprint matches, between 0 and 1.
+    pub score: f64,
+
+    /// The MusicBrainz recordings linked to the track.
+    #[serde(default)]
+    pub recordings: Vec<Recording>,
+}
+
+/// A MusicBrainz recording.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Recording {
+    pub id: String,
+    pub title: Option<String>,
+
+    /// The duration in seconds.
+    pub duration: Option<f64>,
+
+    #[serde(default)]
+    pub artists: Vec<Artist>,
+
+    #[serde(default)]
+    pub releases: Vec<Release>,
+}
+
+/// A MusicBrainz artist.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Artist {
+    pub id: String,
+    pub name: String,
+}
+
+/// A MusicBrainz release a recording appears on.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Release {
+    pub id: String,
+    pub title: Option<String>,
+    pub country: Option<String>,
+    pub date: Option<ReleaseDate>,
+    pub track_count: Option<u32>,
+    pub medium_count: Option<u32>,
+}
+
+/// The date of a release, which is often only partly known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct ReleaseDate {
+    pub year: Option<u16>,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct LookupResponse {
+    results: Vec<LookupResult>,
+}
+
+/// A fingerprint to contribute to AcoustID, with what is known about the recording it was
+/// computed from. Submissions need either a MusicBrainz recording id or enough metadata for
+/// AcoustID to find the recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Submission {
+    pub fingerprint: CompressedFingerprint,
+
+    /// The duration of the whole file in seconds.
+    pub duration: f64,
+
+    /// The MusicBrainz recording id.
+    pub mbid: Option<String>,
+
+    pub track: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub year: Option<u16>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+
+    /// The format of the file, such as `MP3` or `FLAC`.
+    pub file_format: Option<String>,
+
+    /// The bitrate of the file in kbps.
+    pub bitrate: Option<u32>,
+}
+
+impl Submission {
+    pub fn new(fingerprint: CompressedFingerprint, duration: f64) -> Submission {
+        Submission {
+            fingerprint,
+            duration,
+            mbid: None,
+            track: None,
+            artist: None,
+            album: None,
+            album_artist: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            file_format: None,
+            bitrate: None,
+        }
+    }
+
+    /// Adds the fields of the submission to a form, suffixed with its position in the batch.
+    fn append_params(&self, index: usize, params: &mut Vec<(String, String)>) {
+        let mut push = |name: &str, value: String| {
+            params.push((format!("{}.{}", name, index), value));
+        };
+
+        push("fingerprint", self.fingerprint.encode());
+        push("duration", (self.duration.round() as u64).to_string());
+
+        let text_fields = [
+            ("mbid", &self.mbid),
+            ("track", &self.track),
+            ("artist", &self.artist),
+            ("album", &self.album),
+            ("albumartist", &self.album_artist),
+            ("fileformat", &self.file_format),
+        ];
+        for &(name, value) in text_fields.iter() {
+            if let Some(ref value) = *value {
+                push(name, value.clone());
+            }
+        }
+
+        let number_fields = [
+            ("year", self.year.map(u32::from)),
+            ("trackno", self.track_number),
+            ("discno", self.disc_number),
+            ("bitrate", self.bitrate),
+        ];
+        for &(name, value) in number_fields.iter() {
+            if let Some(value) = value {
+                push(name, value.to_string());
+            }
+        }
+    }
+}
+
+/// How far AcoustID has got with importing a submission.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum SubmissionState {
+    Pending,
+    Imported,
+
+    /// A state this client doesn't know about.
+    Other(String),
+}
+
+impl From<String> for SubmissionState {
+    fn from(state: String) -> SubmissionState {
+        match state.as_str() {
+            "pending" => SubmissionState::Pending,
+            "imported" => SubmissionState::Imported,
+            _ => SubmissionState::Other(state),
+        }
+    }
+}
+
+/// The state of a submission.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubmissionStatus {
+    /// The id of the submission, used to poll its status.
+    pub id: u64,
+
+    pub state: SubmissionState,
+
+    /// The AcoustID track id the fingerprint was imported as.
+    pub acoustid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubmissionsResponse {
+    submissions: Vec<SubmissionEntry>,
+}
+
+#[derive(Deserialize)]
+struct SubmissionEntry {
+    /// The position of the fingerprint in the submitted batch, sent as a string.
+    index: Option<Value>,
+    id: u64,
+    status: SubmissionState,
+    result: Option<SubmissionResult>,
+}
+
+#[derive(Deserialize)]
+struct SubmissionResult {
+    id: String,
+}
+
+impl SubmissionEntry {
+    fn index(&self) -> Option<usize> {
+        match self.index {
+            Some(Value::String(ref index)) => index.parse().ok(),
+            Some(ref index) => index.as_u64().map(|index| index as usize),
+            None => None,
+        }
+    }
+
+    fn into_status(self) -> SubmissionStatus {
+        SubmissionStatus {
+            id: self.id,
+            state: self.status,
+            acoustid: self.result.map(|result| result.id),
+        }
+    }
+}
+
+/// A client for the AcoustID web service.
+///
+/// Requests are spaced out to stay within the rate limit of the service, and are retried with an
+/// exponential backoff when the service is unavailable or asks clients to slow down. The client
+/// can be shared between threads.
+pub struct AcoustIdClient {
+    agent: Agent,
+    client_key: String,
+    base_url: String,
+    min_interval: Duration,
+    max_retries: u32,
+    retry_delay: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl AcoustIdClient {
+    /// Creates a client which identifies itself with an application API key registered with
+    /// AcoustID.
+    pub fn new(client_key: &str) -> AcoustIdClient {
+        AcoustIdClient {
+            agent: Agent::new(),
+            client_key: client_key.to_owned(),
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            min_interval: Duration::from_secs_f64(1.0 / DEFAULT_REQUESTS_PER_SECOND),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Sends requests to another server implementing the AcoustID API, such as a local mirror
+    /// or a stand-in used by tests.
+    pub fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+    }
+
+    /// Limits how often requests are sent. A rate of zero disables the limit.
+    pub fn set_rate_limit(&mut self, requests_per_second: f64) {
+        self.min_interval = if requests_per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / requests_per_second)
+        } else {
+            Duration::from_secs(0)
+        };
+    }
+
+    /// Sets how many times a failed request is retried, and the delay before the first retry
+    /// which doubles for each one after it, up to a minute.
+    ///
+    /// Submissions are only retried when the service asks for fewer requests, as other failures
+    /// can happen after the service has stored them.
+    pub fn set_retries(&mut self, max_retries: u32, retry_delay: Duration) {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+    }
+
+    /// Looks up the recordings matching a fingerprint.
+    ///
+    /// `duration` is the duration of the whole file in seconds, as reported by `fpcalc`.
+    pub fn lookup(
+        &self,
+        fingerprint: &CompressedFingerprint,
+        duration: f64,
+    ) -> Result<Vec<LookupResult>, AcoustIdError> {
+        let params = vec![
+            ("client".to_owned(), self.client_key.clone()),
+            ("duration".to_owned(), (duration.round() as u64).to_string()),
+            ("fingerprint".to_owned(), fingerprint.encode()),
+            ("meta".to_owned(), "recordings releases".to_owned()),
+        ];
+
+        let response: LookupResponse = self.request("lookup", &params, Retry::Failures)?;
+
+        Ok(response.results)
+    }
+
+    /// Contributes fingerprints to AcoustID on behalf of the user with the API key `user_key`.
+    /// Large numbers of submissions are sent in several batches.
+    ///
+    /// # Returns
+    /// The status of each submission, in the same order as `submissions`. Submissions are
+    /// imported in the background, so they are usually pending until they are polled later.
+    ///
+    /// # Errors
+    /// `PartlySubmitted` with the statuses of the batches which were accepted when a later batch
+    /// fails.
+    pub fn submit(
+        &self,
+        user_key: &str,
+        submissions: &[Submission],
+    ) -> Result<Vec<SubmissionStatus>, AcoustIdError> {
+        let mut statuses = Vec::with_capacity(submissions.len());
+
+        for batch in submissions.chunks(MAX_SUBMISSION_BATCH) {
+            match self.submit_batch(user_key, batch) {
+                Ok(batch_statuses) => statuses.extend(batch_statuses),
+                Err(error) if statuses.is_empty() => return Err(error),
+                Err(error) => {
+                    return Err(AcoustIdError::PartlySubmitted {
+                        statuses,
+                        error: Box::new(error),
+                    })
+                }
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    fn submit_batch(
+        &self,
+        user_key: &str,
+        batch: &[Submission],
+    ) -> Result<Vec<SubmissionStatus>, AcoustIdError> {
+        let mut params = vec![
+            ("client".to_owned(), self.client_key.clone()),
+            (
+                "clientversion".to_owned(),
+                env!("CARGO_PKG_VERSION").to_owned(),
+            ),
+            ("user".to_owned(), user_key.to_owned()),
+        ];
+        for (index, submission) in batch.iter().enumerate() {
+            submission.append_params(index, &mut params);
+        }
+
+        let response: SubmissionsResponse = self.request("submit", &params, Retry::RateLimited)?;
+        if response.submissions.len() != batch.len() {
+            return Err(AcoustIdError::InvalidResponse(format!(
+                "expected {} submissions, got {}",
+                batch.len(),
+                response.submissions.len()
+            )));
+        }
+
+        let mut entries: Vec<(usize, SubmissionEntry)> = response
+            .submissions
+            .into_iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.index().unwrap_or(position), entry))
+            .collect();
+        entries.sort_by_key(|&(index, _)| index);
+
+        Ok(entries
+            .into_iter()
+            .map(|(_, entry)| entry.into_status())
+            .collect())
+    }
+
+    /// Checks how far AcoustID has got with importing submissions.
+    pub fn submission_status(&self, ids: &[u64]) -> Result<Vec<SubmissionStatus>, AcoustIdError> {
+        let mut params = vec![("client".to_owned(), self.client_key.clone())];
+        params.extend(ids.iter().map(|id| ("id".to_owned(), id.to_string())));
+
+        let response: SubmissionsResponse =
+            self.request("submission_status", &params, Retry::Failures)?;
+
+        Ok(response
+            .submissions
+            .into_iter()
+            .map(SubmissionEntry::into_status)
+            .collect())
+    }
+
+    /// Polls the status of submissions every `interval` until none of them are pending, or
+    /// until they have been polled `max_polls` times.
+    ///
+    /// # Returns
+    /// The last status of each submission.
+    pub fn wait_for_submissions(
+        &self,
+        ids: &[u64],
+        interval: Duration,
+        max_polls: u32,
+    ) -> Result<Vec<SubmissionStatus>, AcoustIdError> {
+        let mut polls = 0;
+        loop {
+            let statuses = self.submission_status(ids)?;
+            polls += 1;
+
+            let pending = statuses
+                .iter()
+                .any(|status| status.state == SubmissionState::Pending);
+            if !pending || polls >= max_polls {
+                return Ok(statuses);
+            }
+
+            thread::sleep(interval);
+        }
+    }
+
+    /// Sends a form to an endpoint of the API and parses the JSON response.
+    fn request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &[(String, String)],
+        retry: Retry,
+    ) -> Result<T, AcoustIdError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let mut form: Vec<(&str, &str)> = vec![("format", "json")];
+        form.extend(
+            params
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_rate_limit();
+
+            let error = match self.agent.post(&url).send_form(&form) {
+                Ok(response) => return parse_response(response),
+                Err(ureq::Error::Status(status, response)) => {
+                    if status == 429 || (status >= 500 && retry == Retry::Failures) {
+                        AcoustIdError::Http(status)
+                    } else if status >= 500 {
+                        return Err(AcoustIdError::Http(status));
+                    } else {
+                        // Invalid requests are rejected with the details of the error in the body.
+                        return Err(match parse_response::<Value>(response) {
+                            Err(error @ AcoustIdError::Service { .. }) => error,
+                            _ => AcoustIdError::Http(status),
+                        });
+                    }
+                }
+                Err(ureq::Error::Transport(transport)) => {
+                    let error = AcoustIdError::Transport(transport.to_string());
+                    if retry != Retry::Failures {
+                        return Err(error);
+                    }
+
+                    error
+                }
+            };
+
+            if attempt >= self.max_retries {
+                return Err(error);
+            }
+
+            thread::sleep(self.retry_delay(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// The delay before retrying a request which has failed `attempt + 1` times.
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        self.retry_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY)
+    }
+
+    /// Blocks until enough time has passed since the last request.
+    fn wait_for_rate_limit(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(last_request) = *last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+
+        *last_request = Some(Instant::now());
+    }
+}
+
+/// Which failed requests are retried.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Retry {
+    /// Requests which are safe to repeat are retried after server and transport errors, and
+    /// when the service asks for fewer requests.
+    Failures,
+
+    /// Requests which change data are only retried when the service asks for fewer requests,
+    /// which it does before handling them.
+    RateLimited,
+}
+
+fn parse_response<T: DeserializeOwned>(response: ureq::Response) -> Result<T, AcoustIdError> {
+    let body = response
+        .into_string()
+        .map_err(|err| AcoustIdError::Transport(err.to_string()))?;
+    let value: Value = serde_json::from_str(&body)
+        .map_err(|err| AcoustIdError::InvalidResponse(err.to_string()))?;
+
+    if value["status"] != "ok" {
+        return Err(AcoustIdError::Service {
+            code: value["error"]["code"].as_i64().unwrap_or(0),
+            message: value["error"]["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_owned(),
+        });
+    }
+
+    serde_json::from_value(value).map_err(|err| AcoustIdError::InvalidResponse(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcoustIdClient, AcoustIdError, ReleaseDate, Submission, SubmissionState};
+    use fingerprinter::CompressedFingerprint;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    const FINGERPRINT: &str = "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog";
+
+    /// Serves the given responses to consecutive requests, and sends back the body of each
+    /// request.
+    fn serve<B: ToString + Send + 'static>(responses: Vec<(u16, B)>) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v2/", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let body = body.to_string();
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if line.starts_with("content-length:") {
+                        content_length = line[15..].trim().parse().unwrap();
+                    }
+                }
+
+                let mut request = vec![0u8; content_length];
+                reader.read_exact(&mut request).unwrap();
+                sender.send(String::from_utf8(request).unwrap()).unwrap();
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} Response\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (url, receiver)
+    }
+
+    fn test_client(url: &str) -> AcoustIdClient {
+        let mut client = AcoustIdClient::new("test-key");
+        client.set_base_url(url);
+        client.set_rate_limit(0.0);
+        client.set_retries(2, Duration::from_millis(1));
+
+        client
+    }
+
+    #[test]
+    fn test_lookup() {
+        let (url, requests) = serve(vec![(
+            200,
+            r#"{"status": "ok", "results": [{"id": "9ff43b6a-4f16-427c-93c2-92307ca505e0",
+                "score": 0.98, "recordings": [{"id": "cd2e7c47-16f5-46c6-a37c-a1eb7bf599ff",
+                "title": "Song", "duration": 213, "artists": [{"id": "a1", "name": "Artist"}],
+                "releases": [{"id": "r1", "title": "Album", "country": "GB",
+                "date": {"year": 2001, "month": 5}, "track_count": 12}]}]},
+                {"id": "a8c5b1c4-0000-0000-0000-000000000000", "score": 0.5}]}"#,
+        )]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let results = test_client(&url).lookup(&fingerprint, 212.6).unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.contains("client=test-key"));
+        assert!(request.contains("duration=213"));
+        assert!(request.contains(&format!("fingerprint={}", FINGERPRINT)));
+
+        assert_eq!(2, results.len());
+        assert_eq!(0.98, results[0].score);
+        assert!(results[1].recordings.is_empty());
+
+        let recording = &results[0].recordings[0];
+        assert_eq!(Some("Song".to_owned()), recording.title);
+        assert_eq!(Some(213.0), recording.duration);
+        assert_eq!("Artist", recording.artists[0].name);
+        assert_eq!(
+            Some(ReleaseDate {
+                year: Some(2001),
+                month: Some(5),
+                day: None,
+            }),
+            recording.releases[0].date
+        );
+    }
+
+    #[test]
+    fn test_retry() {
+        let (url, _requests) = serve(vec![
+            (503, "Service Unavailable"),
+            (429, "Too Many Requests"),
+            (200, r#"{"status": "ok", "results": []}"#),
+        ]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let results = test_client(&url).lookup(&fingerprint, 10.0).unwrap();
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let mut client = AcoustIdClient::new("test-key");
+        client.set_retries(100, Duration::from_secs(1));
+
+        assert_eq!(Duration::from_secs(1), client.retry_delay(0));
+        assert_eq!(Duration::from_secs(8), client.retry_delay(3));
+        assert_eq!(Duration::from_secs(60), client.retry_delay(40));
+        assert_eq!(Duration::from_secs(60), client.retry_delay(99));
+    }
+
+    #[test]
+    fn test_submit_not_repeated() {
+        // The service may have stored a submission which failed with a server error.
+        let (url, requests) = serve(vec![(429, ""), (503, ""), (503, "")]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let submissions = vec![Submission::new(fingerprint, 200.4)];
+
+        assert_eq!(
+            Err(AcoustIdError::Http(503)),
+            test_client(&url).submit("user-key", &submissions)
+        );
+        assert_eq!(2, requests.iter().take(2).count());
+        assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let (url, _requests) = serve(vec![
+            (
+                400,
+                r#"{"status": "error", "error": {"code": 4, "message": "invalid API key"}}"#,
+            ),
+            (503, ""),
+            (503, ""),
+            (503, ""),
+        ]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let client = test_client(&url);
+
+        assert_eq!(
+            Err(AcoustIdError::Service {
+                code: 4,
+                message: "invalid API key".to_owned(),
+            }),
+            client.lookup(&fingerprint, 10.0)
+        );
+        assert_eq!(
+            Err(AcoustIdError::Http(503)),
+            client.lookup(&fingerprint, 10.0)
+        );
+    }
+
+    #[test]
+    fn test_submit() {
+        let first_batch: Vec<String> = (0..50)
+            .map(|index| {
+                format!(
+                    r#"{{"index": "{}", "id": {}, "status": "pending"}}"#,
+                    index,
+                    index + 100
+                )
+            })
+            .collect();
+        let (url, requests) = serve(vec![
+            (
+                200,
+                format!(
+                    r#"{{"status": "ok", "submissions": [{}]}}"#,
+                    first_batch.join(",")
+                ),
+            ),
+            (
+                200,
+                r#"{"status": "ok", "submissions": [
+                    {"index": "1", "id": 151, "status": "pending"},
+                    {"index": "0", "id": 150, "status": "imported", "result": {"id": "t1"}}]}"#
+                    .to_owned(),
+            ),
+        ]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let mut submissions = vec![Submission::new(fingerprint, 200.4); 52];
+        submissions[0].mbid = Some("cd2e7c47-16f5-46c6-a37c-a1eb7bf599ff".to_owned());
+        submissions[51].artist = Some("Artist".to_owned());
+        submissions[51].year = Some(2001);
+
+        let statuses = test_client(&url).submit("user-key", &submissions).unwrap();
+
+        let first_request = requests.recv().unwrap();
+        assert!(first_request.contains("user=user-key"));
+        assert!(first_request.contains(&format!("fingerprint.49={}", FINGERPRINT)));
+        assert!(first_request.contains("duration.0=200"));
+        assert!(first_request.contains("mbid.0=cd2e7c47-16f5-46c6-a37c-a1eb7bf599ff"));
+        assert!(!first_request.contains("fingerprint.50"));
+
+        let second_request = requests.recv().unwrap();
+        assert!(second_request.contains("artist.1=Artist"));
+        assert!(second_request.contains("year.1=2001"));
+
+        assert_eq!(52, statuses.len());
+        assert_eq!(149, statuses[49].id);
+        assert_eq!(150, statuses[50].id);
+        assert_eq!(SubmissionState::Imported, statuses[50].state);
+        assert_eq!(Some("t1".to_owned()), statuses[50].acoustid);
+        assert_eq!(SubmissionState::Pending, statuses[51].state);
+    }
+
+    #[test]
+    fn test_submit_partly_accepted() {
+        let first_batch: Vec<String> = (0..50)
+            .map(|index| format!(r#"{{"id": {}, "status": "pending"}}"#, index + 100))
+            .collect();
+        let (url, _requests) = serve(vec![
+            (
+                200,
+                format!(
+                    r#"{{"status": "ok", "submissions": [{}]}}"#,
+                    first_batch.join(",")
+                ),
+            ),
+            (503, "".to_owned()),
+        ]);
+
+        let fingerprint: CompressedFingerprint = FINGERPRINT.parse().unwrap();
+        let submissions = vec![Submission::new(fingerprint, 200.4); 60];
+
+        match test_client(&url).submit("user-key", &submissions) {
+            Err(AcoustIdError::PartlySubmitted { statuses, error }) => {
+                assert_eq!(50, statuses.len());
+                assert_eq!(149, statuses[49].id);
+                assert_eq!(AcoustIdError::Http(503), *error);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_wait_for_submissions() {
+        let (url, requests) = serve(vec![
+            (
+                200,
+                r#"{"status": "ok", "submissions": [{"id": 1, "status": "imported",
+                    "result": {"id": "t1"}}, {"id": 2, "status": "pending"}]}"#,
+            ),
+            (
+                200,
+                r#"{"status": "ok", "submissions": [{"id": 1, "status": "imported",
+                    "result": {"id": "t1"}}, {"id": 2, "status": "imported",
+                    "result": {"id": "t2"}}]}"#,
+            ),
+        ]);
+
+        let statuses = test_client(&url)
+            .wait_for_submissions(&[1, 2], Duration::from_millis(1), 5)
+            .unwrap();
+
+        assert!(requests.recv().unwrap().contains("id=1&id=2"));
+        assert_eq!(Some("t2".to_owned()), statuses[1].acoustid);
+        assert_eq!(SubmissionState::Imported, statuses[1].state);
+    }
+}
@@ -0,0 +1,164 @@
+use chroma_filter::FILTER_COEFFICIENTS;
+use classifiers::{self, Classifiers};
+use fingerprinter::{FRAME_OVERLAP, FRAME_SIZE, TARGET_SAMPLE_RATE};
+use silence_remover::SILENCE_WINDOW;
+
+/// The fingerprinting algorithms implemented by upstream chromaprint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Test1,
+    #[default]
+    Test2,
+    Test3,
+    Test4,
+    Test5,
+}
+
+impl Algorithm {
+    /// The identifier of the algorithm written into the header of compressed fingerprints.
+    pub fn id(&self) -> u8 {
+        match *self {
+            Algorithm::Test1 => 0,
+            Algorithm::Test2 => 1,
+            Algorithm::Test3 => 2,
+            Algorithm::Test4 => 3,
+            Algorithm::Test5 => 4,
+        }
+    }
+
+    /// Looks up an algorithm by the identifier used in compressed fingerprints.
+    pub fn from_id(id: u8) -> Option<Algorithm> {
+        match id {
+            0 => Some(Algorithm::Test1),
+            1 => Some(Algorithm::Test2),
+            2 => Some(Algorithm::Test3),
+            3 => Some(Algorithm::Test4),
+            4 => Some(Algorithm::Test5),
+            _ => None,
+        }
+    }
+
+    /// The duration of audio in seconds between consecutive items of a fingerprint.
+    pub fn item_duration(&self) -> f64 {
+        let configuration = self.configuration();
+        let step = configuration.frame_size - configuration.frame_overlap;
+
+        step as f64 / TARGET_SAMPLE_RATE as f64
+    }
+
+    /// The duration of audio in seconds between the start of the input and the point the first
+    /// item of a fingerprint describes, caused by the windows of the chroma filter and the
+    /// classifiers.
+    pub fn delay(&self) -> f64 {
+        let configuration = self.configuration();
+        let max_filter_width = configuration
+            .classifiers
+            .iter()
+            .map(|(filter, _)| filter.width())
+            .max()
+            .unwrap_or(1);
+        let items = (configuration.filter_coefficients.len() - 1) + (max_filter_width - 1);
+
+        items as f64 * self.item_duration()
+            + configuration.frame_overlap as f64 / TARGET_SAMPLE_RATE as f64
+    }
+
+    pub fn configuration(&self) -> Configuration {
+        match *self {
+            Algorithm::Test1 => Configuration {
+                classifiers: classifiers::get_test1_classifier(),
+                ..Configuration::default()
+            },
+            Algorithm::Test2 => Configuration::default(),
+            Algorithm::Test3 => Configuration {
+                classifiers: classifiers::get_test3_classifier(),
+                interpolate: true,
+                ..Configuration::default()
+            },
+            Algorithm::Test4 => Configuration {
+                silence_removal: Some(SilenceRemoval {
+                    threshold: 50,
+                    window: SILENCE_WINDOW,
+                }),
+                ..Configuration::default()
+            },
+            Algorithm::Test5 => Configuration {
+                frame_size: FRAME_SIZE / 2,
+                frame_overlap: FRAME_SIZE / 2 - FRAME_SIZE / 4,
+                ..Configuration::default()
+            },
+        }
+    }
+}
+
+/// The parameters of the fingerprinting pipeline for an algorithm.
+pub struct Configuration {
+    pub classifiers: Classifiers,
+    pub filter_coefficients: &'static [f64],
+    pub interpolate: bool,
+    pub frame_size: usize,
+    pub frame_overlap: usize,
+    pub silence_removal: Option<SilenceRemoval>,
+}
+
+/// The parameters of the silence removal stage, which runs on the audio resampled to 11025 Hz.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SilenceRemoval {
+    pub threshold: u16,
+    pub window: usize,
+}
+
+impl Default for Configuration {
+    fn default() -> Configuration {
+        Configuration {
+            classifiers: classifiers::get_test2_classifier(),
+            filter_coefficients: &FILTER_COEFFICIENTS,
+            interpolate: false,
+            frame_size: FRAME_SIZE,
+            frame_overlap: FRAME_OVERLAP,
+            silence_removal: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Algorithm;
+
+    #[test]
+    fn test_id_round_trip() {
+        let algorithms = [
+            Algorithm::Test1,
+            Algorithm::Test2,
+            Algorithm::Test3,
+            Algorithm::Test4,
+            Algorithm::Test5,
+        ];
+
+        for algorithm in algorithms.iter() {
+            assert_eq!(Some(*algorithm), Algorithm::from_id(algorithm.id()));
+        }
+        assert_eq!(None, Algorithm::from_id(5));
+    }
+
+    #[test]
+    fn test_item_duration() {
+        assert_abs_diff_eq!(0.1238095, Algorithm::Test2.item_duration(), epsilon = 1e-7);
+        assert_abs_diff_eq!(0.0928798, Algorithm::Test5.item_duration(), epsilon = 1e-7);
+    }
+
+    #[test]
+    fn test_delay() {
+        // 4 items for the chroma filter and 15 for the widest classifier, plus the frame overlap.
+        assert_abs_diff_eq!(
+            (19.0 * 1365.0 + 2731.0) / 11025.0,
+            Algorithm::Test2.delay(),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_default() {
+        assert_eq!(1, Algorithm::default().id());
+    }
+}
@@ -1,4 +1,9 @@
+use alloc::vec::Vec;
+use decimator::Decimator;
+use downmixer::Downmixer;
+use error::ChromaprintError;
 use resampler::Resampler;
+use silence_remover::SilenceRemover;
 use slicer::Slicer;
 
 const MAX_BUFFER_SIZE: usize = 1024 * 32;
@@ -7,57 +12,167 @@ const RESAMPLE_PHASE_SHIFT: i32 = 8;
 const RESAMPLE_LINEAR: bool = false;
 const RESAMPLE_SAMPLE_CUTOFF: f64 = 0.8;
 
+/// Inputs sampled faster than this are decimated before being resampled. Resampling by large
+/// ratios needs very long filters which makes it slow.
+const MAX_RESAMPLER_INPUT_RATE: u32 = 48000;
+
+#[derive(Clone)]
 pub struct AudioProcessor {
-    slicer: Option<Slicer<i16>>,
+    downmixer: Option<Downmixer>,
+    silence_remover: Option<SilenceRemover>,
+    decimators: Vec<Decimator>,
+    slicer: Slicer<i16>,
     resampler: Resampler,
 }
 
 impl AudioProcessor {
-    pub fn new(target_sample_rate: u16, input_sample_rate: u16) -> AudioProcessor {
-        AudioProcessor {
-            slicer: Some(Slicer::new(MAX_BUFFER_SIZE)),
+    pub fn new(
+        target_sample_rate: u32,
+        input_sample_rate: u32,
+        input_channels: u16,
+    ) -> Result<AudioProcessor, ChromaprintError> {
+        if input_channels == 0 {
+            return Err(ChromaprintError::InvalidChannelCount(input_channels));
+        }
+
+        let mut decimators = Vec::new();
+        let mut resampler_input_rate = input_sample_rate;
+        while resampler_input_rate > MAX_RESAMPLER_INPUT_RATE && resampler_input_rate & 1 == 0 {
+            decimators.push(Decimator::new());
+            resampler_input_rate /= 2;
+        }
+
+        Ok(AudioProcessor {
+            downmixer: if input_channels == 1 {
+                None
+            } else {
+                Some(Downmixer::new(input_channels))
+            },
+            silence_remover: None,
+            decimators,
+            slicer: Slicer::new(MAX_BUFFER_SIZE),
             resampler: Resampler::new(
-                target_sample_rate as i32,
-                input_sample_rate as i32,
+                target_sample_rate,
+                resampler_input_rate,
                 RESAMPLE_FILTER_LENGTH,
                 RESAMPLE_PHASE_SHIFT,
                 RESAMPLE_LINEAR,
                 RESAMPLE_SAMPLE_CUTOFF,
-            ),
+            )?,
+        })
+    }
+
+    /// Drops the leading silence of the resampled audio, like the silence removal stage which
+    /// upstream chromaprint runs after its audio processor. Only input fed after this is called
+    /// is affected.
+    pub fn remove_silence(&mut self, threshold: u16, window: usize) {
+        self.silence_remover = Some(SilenceRemover::new(threshold, window));
+    }
+
+    /// Consumes interleaved samples.
+    pub fn feed<C: FnMut(Vec<i16>)>(&mut self, data: &[i16], consumer: C) {
+        let downmixed;
+        let data = match self.downmixer {
+            Some(ref mut downmixer) => {
+                downmixed = downmixer.process(data);
+                &downmixed[..]
+            }
+            None => data,
+        };
+
+        if self.decimators.is_empty() {
+            self.resample(data, consumer);
+            return;
+        }
+
+        let decimated = decimate(&mut self.decimators, data);
+        self.resample(&decimated, consumer);
+    }
+
+    /// Transcodes any un-transcoded samples and passes them to `consumer`.
+    pub fn flush<C: FnMut(Vec<i16>)>(&mut self, mut consumer: C) {
+        for idx in 0..self.decimators.len() {
+            let flushed = self.decimators[idx].flush();
+            let decimated = decimate(&mut self.decimators[(idx + 1)..], &flushed);
+            self.resample(&decimated, &mut consumer);
+        }
+
+        let remaining = self.slicer.flush();
+        if remaining.is_empty() {
+            return;
         }
+
+        let (_, dst) = resample_slice(&mut self.resampler, remaining);
+        consumer(remove_silence(&mut self.silence_remover, dst));
     }
 
-    pub fn feed<C: FnMut(Vec<i16>)>(&mut self, data: &[i16], mut consumer: C) {
-        let mut slicer = self.slicer.take().unwrap();
+    fn resample<C: FnMut(Vec<i16>)>(&mut self, data: &[i16], mut consumer: C) {
+        let resampler = &mut self.resampler;
+        let silence_remover = &mut self.silence_remover;
 
-        slicer.process(data, |src| {
-            let (consumed_size, dst) = self.resample_slice(src);
-            consumer(dst);
+        self.slicer.process(data, |src| {
+            let (consumed_size, dst) = resample_slice(resampler, src);
+            consumer(remove_silence(silence_remover, dst));
             consumed_size
         });
+    }
+}
 
-        self.slicer = Some(slicer);
+fn remove_silence(silence_remover: &mut Option<SilenceRemover>, mut samples: Vec<i16>) -> Vec<i16> {
+    if let Some(ref mut silence_remover) = *silence_remover {
+        let silence = samples.len() - silence_remover.process(&samples).len();
+        samples.drain(..silence);
     }
 
-    /// Transcodes any un-transcoded samples and returns if any are left.
-    pub fn flush(&mut self) -> Option<Vec<i16>> {
-        let mut slicer = self.slicer.take().unwrap();
-        let remaining = slicer.flush();
-        self.slicer = Some(slicer);
-        if remaining.is_empty() {
-            return None;
-        }
+    samples
+}
+
+fn resample_slice(resampler: &mut Resampler, src: Vec<i16>) -> (usize, Vec<i16>) {
+    let mut dst = vec![0i16; MAX_BUFFER_SIZE];
 
-        let (_, dst) = self.resample_slice(remaining);
-        Some(dst)
+    let (consumed_size, last_idx) = resampler.resample(&src, &mut dst);
+    dst.truncate(last_idx + 1);
+
+    (consumed_size, dst)
+}
+
+/// Passes `data` through each of the decimators in turn.
+fn decimate(decimators: &mut [Decimator], data: &[i16]) -> Vec<i16> {
+    let mut data = data.to_vec();
+    for decimator in decimators.iter_mut() {
+        data = decimator.process(&data);
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioProcessor;
+    use silence_remover::{SilenceRemover, SILENCE_WINDOW};
+
+    fn process(audio_processor: &mut AudioProcessor, data: &[i16]) -> Vec<i16> {
+        let mut output = Vec::new();
+        audio_processor.feed(data, |samples| output.extend(samples));
+        audio_processor.flush(|samples| output.extend(samples));
+
+        output
     }
 
-    fn resample_slice(&mut self, src: Vec<i16>) -> (usize, Vec<i16>) {
-        let mut dst = vec![0i16; MAX_BUFFER_SIZE];
+    #[test]
+    fn test_remove_silence_after_resampling() {
+        let mut data = vec![0i16; 4410];
+        data.extend((0..44100).map(|idx| ((idx % 100) as i16 - 50) * 20));
+
+        let resampled = process(&mut AudioProcessor::new(11025, 44100, 1).unwrap(), &data);
 
-        let (consumed_size, last_idx) = self.resampler.resample(&src, &mut dst);
-        dst.truncate(last_idx + 1);
+        let mut audio_processor = AudioProcessor::new(11025, 44100, 1).unwrap();
+        audio_processor.remove_silence(50, SILENCE_WINDOW);
+        let trimmed = process(&mut audio_processor, &data);
 
-        (consumed_size, dst)
+        // The window is counted in samples of the resampled audio.
+        let expected = SilenceRemover::new(50, SILENCE_WINDOW).process(&resampled);
+        assert!(expected.len() < resampled.len());
+        assert_eq!(expected, &trimmed[..]);
     }
 }
@@ -0,0 +1,306 @@
+//! Fingerprints many sources at once on a rayon thread pool.
+
+use rayon::prelude::*;
+use std::collections::HashMap;
+#[cfg(not(feature = "decode"))]
+use std::fs::File;
+use std::io;
+#[cfg(not(feature = "decode"))]
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use algorithm::Algorithm;
+#[cfg(feature = "decode")]
+use decode::AudioDecoder;
+use error::ChromaprintError;
+use fingerprinter::{Fingerprint, Fingerprinter};
+use sample::Sample;
+#[cfg(not(feature = "decode"))]
+use wav::WavReader;
+
+/// The number of frames read from a file at a time.
+#[cfg(not(feature = "decode"))]
+const READ_FRAMES: usize = 4096;
+
+/// Fingerprints batches of audio in parallel.
+///
+/// The filter bank of the resampler, the window and plan of the FFT and the note tables are
+/// computed once for each audio format and shared by all the fingerprinters created for it.
+pub struct BatchFingerprinter {
+    algorithm: Algorithm,
+    max_duration: Option<f64>,
+
+    /// Unused fingerprinters for each sample rate and channel count, cloned for each source.
+    fingerprinters: Mutex<HashMap<(u32, u16), Fingerprinter>>,
+}
+
+impl BatchFingerprinter {
+    pub fn new(algorithm: Algorithm) -> BatchFingerprinter {
+        BatchFingerprinter {
+            algorithm,
+            max_duration: None,
+            fingerprinters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Only fingerprints the first `max_duration` seconds of each source, like fpcalc does. The
+    /// whole source is fingerprinted when it is `None`, which is the default.
+    pub fn set_max_duration(&mut self, max_duration: Option<f64>) {
+        self.max_duration = max_duration;
+    }
+
+    pub fn max_duration(&self) -> Option<f64> {
+        self.max_duration
+    }
+
+    /// Creates a fingerprinter which shares its precomputed tables with the others created for
+    /// audio in the same format.
+    pub fn fingerprinter(
+        &self,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Fingerprinter, ChromaprintError> {
+        let format = (sample_rate, channels);
+        if let Some(fingerprinter) = self.fingerprinters.lock().unwrap().get(&format) {
+            return Ok(fingerprinter.clone());
+        }
+
+        // The tables are computed without holding the lock, so the other workers aren't blocked.
+        // When several workers start on the same new format at once, all of them use the tables
+        // of the first one to finish.
+        let fingerprinter = Fingerprinter::with_algorithm(sample_rate, channels, self.algorithm)?;
+
+        Ok(self
+            .fingerprinters
+            .lock()
+            .unwrap()
+            .entry(format)
+            .or_insert(fingerprinter)
+            .clone())
+    }
+
+    /// Fingerprints each of `sources` on the current rayon thread pool. Call it from
+    /// `ThreadPool::install` to use another pool.
+    ///
+    /// # Returns
+    /// For each source in the order they were given, either its fingerprint or the error which
+    /// stopped it from being fingerprinted.
+    pub fn fingerprint_all<I>(&self, sources: I) -> Vec<io::Result<BatchFingerprint>>
+    where
+        I: IntoIterator,
+        I::Item: AudioSource,
+    {
+        let sources: Vec<I::Item> = sources.into_iter().collect();
+
+        sources
+            .into_par_iter()
+            .map(|source| source.fingerprint(self))
+            .collect()
+    }
+}
+
+/// The fingerprint of a source in a batch. Only the fingerprint is kept, so the buffers of the
+/// fingerprinter are freed as soon as each source is done.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchFingerprint {
+    /// The raw sub-fingerprints.
+    pub fingerprint: Vec<u32>,
+
+    pub algorithm: Algorithm,
+
+    /// The duration of the audio in seconds.
+    pub duration: f64,
+}
+
+impl BatchFingerprint {
+    fn new(mut fingerprinter: Fingerprinter, duration: f64) -> io::Result<BatchFingerprint> {
+        fingerprinter.finish().map_err(invalid_input)?;
+
+        Ok(BatchFingerprint {
+            fingerprint: fingerprinter.fingerprint().0.to_vec(),
+            algorithm: fingerprinter.algorithm(),
+            duration,
+        })
+    }
+
+    /// Borrows the fingerprint, to compress or compare it.
+    pub fn as_fingerprint(&self) -> Fingerprint<'_> {
+        Fingerprint(&self.fingerprint, self.algorithm)
+    }
+}
+
+/// Audio which can be fingerprinted in a batch.
+pub trait AudioSource: Send {
+    /// Feeds up to `batch.max_duration()` seconds of the audio to a fingerprinter from `batch`.
+    fn fingerprint(self, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint>;
+}
+
+/// Interleaved samples which have already been decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawAudio<S> {
+    pub samples: Vec<S>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl<S: Sample + Send> AudioSource for RawAudio<S> {
+    fn fingerprint(self, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint> {
+        let mut fingerprinter = batch
+            .fingerprinter(self.sample_rate, self.channels)
+            .map_err(invalid_input)?;
+
+        let channels = self.channels as usize;
+        let frames = self.samples.len() / channels;
+        let fed = match batch.max_duration() {
+            Some(max_duration) => frames.min((max_duration * self.sample_rate as f64) as usize),
+            None => frames,
+        };
+        fingerprinter
+            .feed(&self.samples[..(fed * channels)])
+            .map_err(invalid_input)?;
+
+        BatchFingerprint::new(fingerprinter, frames as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Files are decoded with `AudioDecoder` when the `decode` feature is enabled. Otherwise only WAV
+/// files are supported.
+impl AudioSource for PathBuf {
+    fn fingerprint(self, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint> {
+        fingerprint_path(&self, batch)
+    }
+}
+
+impl AudioSource for &Path {
+    fn fingerprint(self, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint> {
+        fingerprint_path(self, batch)
+    }
+}
+
+#[cfg(feature = "decode")]
+fn fingerprint_path(path: &Path, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let mut fingerprinter = batch
+        .fingerprinter(decoder.sample_rate(), decoder.channels())
+        .map_err(invalid_input)?;
+
+    let duration = decoder.feed(&mut fingerprinter, batch.max_duration())?;
+
+    BatchFingerprint::new(fingerprinter, duration)
+}
+
+#[cfg(not(feature = "decode"))]
+fn fingerprint_path(path: &Path, batch: &BatchFingerprinter) -> io::Result<BatchFingerprint> {
+    let mut reader = WavReader::new(BufReader::new(File::open(path)?))?;
+    let spec = reader.spec();
+    let mut fingerprinter = batch
+        .fingerprinter(spec.sample_rate, spec.channels)
+        .map_err(invalid_input)?;
+
+    let frame_limit = batch
+        .max_duration()
+        .map(|duration| (duration * spec.sample_rate as f64) as usize);
+    let mut frames = 0;
+
+    loop {
+        let max_frames = match frame_limit {
+            Some(frame_limit) => READ_FRAMES.min(frame_limit - frames),
+            None => READ_FRAMES,
+        };
+        if max_frames == 0 {
+            break;
+        }
+
+        let samples = reader.read_samples(max_frames)?;
+        if samples.is_empty() {
+            break;
+        }
+
+        frames += samples.len() / spec.channels as usize;
+        fingerprinter.feed(&samples).map_err(invalid_input)?;
+    }
+
+    let duration = reader
+        .duration()
+        .unwrap_or(frames as f64 / spec.sample_rate as f64);
+
+    BatchFingerprint::new(fingerprinter, duration)
+}
+
+fn invalid_input(error: ChromaprintError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchFingerprinter, RawAudio};
+    use algorithm::Algorithm;
+    use fingerprinter::Fingerprinter;
+    use std::env;
+    use std::error::Error;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use tests;
+
+    #[test]
+    fn test_fingerprint_all() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+
+        let mut expected = Fingerprinter::with_algorithm(44100, 2, Algorithm::Test1)?;
+        expected.feed(&samples)?;
+        expected.finish()?;
+        let expected_duration = samples.len() as f64 / 2.0 / 44100.0;
+
+        let batch = BatchFingerprinter::new(Algorithm::Test1);
+        let sources = vec![
+            RawAudio {
+                samples: samples.clone(),
+                sample_rate: 44100,
+                channels: 2,
+            },
+            RawAudio {
+                samples: samples.clone(),
+                sample_rate: 0,
+                channels: 2,
+            },
+            RawAudio {
+                samples,
+                sample_rate: 44100,
+                channels: 2,
+            },
+        ];
+        let results = batch.fingerprint_all(sources);
+
+        assert_eq!(3, results.len());
+        for idx in [0, 2].iter() {
+            let result = results[*idx].as_ref().unwrap();
+            assert_eq!(expected.fingerprint().0, &result.fingerprint[..]);
+            assert_eq!(Algorithm::Test1, result.algorithm);
+            assert_abs_diff_eq!(expected_duration, result.duration, epsilon = 1e-9);
+        }
+        assert_eq!(
+            Some(ErrorKind::InvalidInput),
+            results[1].as_ref().err().map(|err| err.kind())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let batch = BatchFingerprinter::new(Algorithm::default());
+        let results = batch.fingerprint_all(vec![Path::new("does-not-exist.wav")]);
+
+        assert_eq!(
+            Some(ErrorKind::NotFound),
+            results[0].as_ref().err().map(|err| err.kind())
+        );
+    }
+}
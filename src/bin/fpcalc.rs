@@ -0,0 +1,744 @@
+//! A command line tool which prints fingerprints in the same formats as chromaprint's `fpcalc`.
+
+extern crate chromaprint;
+
+#[cfg(feature = "decode")]
+use chromaprint::AudioDecoder;
+use chromaprint::{Algorithm, Fingerprinter, WavReader};
+use std::cmp;
+use std::env;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Write};
+use std::process;
+
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+const DEFAULT_CHANNELS: u16 = 2;
+const DEFAULT_MAX_DURATION: f64 = 120.0;
+const READ_SIZE: usize = 64 * 1024;
+
+const USAGE: &str = "Usage: fpcalc [OPTIONS] FILE [FILE...]
+
+Generate fingerprints from audio files/streams.
+
+Input is read as a WAV file, a compressed audio file when built with the decode
+feature, or otherwise as raw signed 16-bit little-endian PCM. Use - to read from
+the standard input.
+
+Options:
+  -rate NUM      Set the sample rate of raw input audio (default 44100)
+  -channels NUM  Set the number of channels in raw input audio (default 2)
+  -length SECS   Restrict the duration of the processed input audio (default 120)
+  -chunk SECS    Split the input audio into chunks of this duration
+  -overlap       Overlap the chunks slightly to make sure audio on the edges is fingerprinted
+  -algorithm NUM Set the algorithm method (default 2)
+  -raw           Output fingerprints in the uncompressed format
+  -signed        Change the uncompressed format from unsigned to signed integers
+  -json          Print the output in JSON format
+  -text          Print the output in text format
+  -plain         Print just the fingerprint in text format
+  -version       Print version information";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Plain,
+}
+
+struct Options {
+    sample_rate: u32,
+    channels: u16,
+    max_duration: f64,
+    chunk_duration: f64,
+    overlap: bool,
+    algorithm: Algorithm,
+    raw: bool,
+    signed: bool,
+    format: Format,
+    files: Vec<String>,
+}
+
+enum Command {
+    Fingerprint(Options),
+    Help,
+    Version,
+}
+
+fn parse_arguments<I: Iterator<Item = String>>(mut arguments: I) -> Result<Command, String> {
+    let mut options = Options {
+        sample_rate: DEFAULT_SAMPLE_RATE,
+        channels: DEFAULT_CHANNELS,
+        max_duration: DEFAULT_MAX_DURATION,
+        chunk_duration: 0.0,
+        overlap: false,
+        algorithm: Algorithm::default(),
+        raw: false,
+        signed: false,
+        format: Format::Text,
+        files: Vec::new(),
+    };
+
+    while let Some(argument) = arguments.next() {
+        if argument == "-" || !argument.starts_with('-') {
+            options.files.push(argument);
+            continue;
+        }
+
+        let name = argument.trim_start_matches('-');
+        match name {
+            "rate" => options.sample_rate = parse_value(name, arguments.next())?,
+            "channels" => options.channels = parse_value(name, arguments.next())?,
+            "length" => options.max_duration = parse_duration(name, arguments.next())?,
+            "chunk" => options.chunk_duration = parse_duration(name, arguments.next())?,
+            "overlap" => options.overlap = true,
+            "algorithm" => {
+                let value: u8 = parse_value(name, arguments.next())?;
+                options.algorithm = value
+                    .checked_sub(1)
+                    .and_then(Algorithm::from_id)
+                    .ok_or_else(|| format!("Invalid value for -algorithm: {}", value))?;
+            }
+            "raw" => options.raw = true,
+            "signed" => options.signed = true,
+            "json" => options.format = Format::Json,
+            "text" => options.format = Format::Text,
+            "plain" => options.format = Format::Plain,
+            "version" | "v" => return Ok(Command::Version),
+            "help" | "h" => return Ok(Command::Help),
+            _ => return Err(format!("Unknown option: {}", argument)),
+        }
+    }
+
+    if options.files.is_empty() {
+        return Err("No input files".to_owned());
+    }
+
+    Ok(Command::Fingerprint(options))
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for -{}", name))?;
+
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for -{}: {}", name, value))
+}
+
+fn parse_duration(name: &str, value: Option<String>) -> Result<f64, String> {
+    let duration: f64 = parse_value(name, value)?;
+    if !(duration >= 0.0) {
+        return Err(format!("Invalid value for -{}: {}", name, duration));
+    }
+
+    Ok(duration)
+}
+
+/// PCM audio to fingerprint, positioned at the first sample.
+struct Input {
+    source: Source,
+    sample_rate: u32,
+    channels: u16,
+
+    /// The duration in seconds recorded in the header of the input, if any.
+    duration: Option<f64>,
+}
+
+enum Source {
+    Wav(WavReader<Box<dyn Read>>),
+
+    #[cfg(feature = "decode")]
+    Decoded(AudioDecoder),
+
+    /// Signed 16-bit little-endian samples, and the bytes of a partly read frame.
+    Raw(Box<dyn Read>, Vec<u8>),
+}
+
+impl Input {
+    fn open(path: &str, options: &Options) -> Result<Input, String> {
+        let mut reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin())
+        } else {
+            Box::new(File::open(path).map_err(|err| format!("Could not open {}: {}", path, err))?)
+        };
+
+        let mut header = Vec::new();
+        (&mut reader)
+            .take(12)
+            .read_to_end(&mut header)
+            .map_err(|err| err.to_string())?;
+        let reader: Box<dyn Read> = Box::new(Cursor::new(header.clone()).chain(reader));
+
+        if header.len() == 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+            let reader = WavReader::new(reader).map_err(|err| err.to_string())?;
+            let spec = reader.spec();
+
+            return Ok(Input {
+                duration: reader.duration(),
+                source: Source::Wav(reader),
+                sample_rate: spec.sample_rate,
+                channels: spec.channels,
+            });
+        }
+
+        // Files in any other format symphonia recognises are decoded, the rest are raw PCM.
+        #[cfg(feature = "decode")]
+        {
+            if path != "-" {
+                if let Ok(decoder) = AudioDecoder::open(path) {
+                    return Ok(Input {
+                        sample_rate: decoder.sample_rate(),
+                        channels: decoder.channels(),
+                        duration: decoder.duration(),
+                        source: Source::Decoded(decoder),
+                    });
+                }
+            }
+        }
+
+        Ok(Input {
+            source: Source::Raw(reader, Vec::new()),
+            sample_rate: options.sample_rate,
+            channels: options.channels,
+            duration: None,
+        })
+    }
+
+    /// Reads the next block of whole frames.
+    ///
+    /// # Returns
+    /// An empty vector at the end of the input.
+    fn read_samples(&mut self) -> Result<Vec<i16>, String> {
+        let frame_size = 2 * self.channels as usize;
+
+        match self.source {
+            Source::Wav(ref mut reader) => reader
+                .read_samples(READ_SIZE / frame_size)
+                .map_err(|err| err.to_string()),
+            #[cfg(feature = "decode")]
+            Source::Decoded(ref mut decoder) => {
+                decoder.read_samples().map_err(|err| err.to_string())
+            }
+            Source::Raw(ref mut reader, ref mut bytes) => {
+                let mut buffer = vec![0u8; READ_SIZE];
+                while bytes.len() < frame_size {
+                    let read = match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(read) => read,
+                        Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(err.to_string()),
+                    };
+                    bytes.extend_from_slice(&buffer[..read]);
+                }
+
+                let whole_size = bytes.len() - bytes.len() % frame_size;
+                let samples = bytes[..whole_size]
+                    .chunks(2)
+                    .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                    .collect();
+                bytes.drain(..whole_size);
+
+                Ok(samples)
+            }
+        }
+    }
+}
+
+/// A fingerprint of part of the input.
+struct Chunk {
+    fingerprinter: Fingerprinter,
+
+    /// The position of the first frame fed to the fingerprinter.
+    start: usize,
+
+    /// The number of frames fed to the fingerprinter.
+    frames: usize,
+}
+
+impl Chunk {
+    fn new(input: &Input, options: &Options, start: usize) -> Result<Chunk, String> {
+        let fingerprinter =
+            Fingerprinter::with_algorithm(input.sample_rate, input.channels, options.algorithm)
+                .map_err(|err| err.to_string())?;
+
+        Ok(Chunk {
+            fingerprinter,
+            start,
+            frames: 0,
+        })
+    }
+
+    fn feed(&mut self, samples: &[i16], channels: usize) -> Result<(), String> {
+        self.frames += samples.len() / channels;
+
+        self.fingerprinter
+            .feed(samples)
+            .map_err(|err| err.to_string())
+    }
+}
+
+fn process_file(path: &str, options: &Options, out: &mut dyn Write) -> Result<(), String> {
+    process_input(Input::open(path, options)?, options, out)
+}
+
+fn process_input(mut input: Input, options: &Options, out: &mut dyn Write) -> Result<(), String> {
+    let channels = input.channels as usize;
+    let sample_rate = input.sample_rate as f64;
+
+    let frame_limit = (options.max_duration * sample_rate) as usize;
+    let chunk_limit = (options.chunk_duration * sample_rate) as usize;
+    let overlap_frames = if options.overlap && chunk_limit > 0 {
+        (options.algorithm.delay() * sample_rate) as usize
+    } else {
+        0
+    };
+
+    let mut chunk = Chunk::new(&input, options, 0)?;
+    let mut chunk_end = chunk_limit;
+    let mut first = true;
+    let mut processed_frames = 0;
+    let mut overlap: Vec<i16> = Vec::new();
+
+    loop {
+        if frame_limit > 0 && processed_frames == frame_limit {
+            // The input may be a stream which never ends, so the rest of it isn't read.
+            break;
+        }
+
+        let samples = input.read_samples()?;
+        if samples.is_empty() {
+            break;
+        }
+
+        let mut samples = &samples[..];
+        while !samples.is_empty() {
+            let mut frames = samples.len() / channels;
+            if frame_limit > 0 {
+                frames = cmp::min(frames, frame_limit - processed_frames);
+            }
+            if chunk_limit > 0 {
+                frames = cmp::min(frames, chunk_end - processed_frames);
+            }
+            if frames == 0 {
+                // The rest of the block is past the limit.
+                break;
+            }
+
+            let (fed, rest) = samples.split_at(frames * channels);
+            chunk.feed(fed, channels)?;
+            processed_frames += frames;
+            samples = rest;
+
+            if overlap_frames > 0 {
+                overlap.extend_from_slice(fed);
+                let excess = overlap.len().saturating_sub(overlap_frames * channels);
+                overlap.drain(..excess);
+            }
+
+            if chunk_limit > 0 && processed_frames == chunk_end {
+                print_chunk(&mut chunk, options, first, sample_rate, out)?;
+                first = false;
+
+                // The next chunk starts with the end of this one, so the items which need audio
+                // from both sides of the boundary aren't lost.
+                chunk = Chunk::new(&input, options, processed_frames - overlap.len() / channels)?;
+                chunk.feed(&overlap, channels)?;
+                chunk_end += chunk_limit;
+            }
+        }
+    }
+
+    if chunk_limit > 0 {
+        if first || processed_frames > chunk_end - chunk_limit {
+            print_chunk(&mut chunk, options, first, sample_rate, out)?;
+        }
+
+        return Ok(());
+    }
+
+    chunk
+        .fingerprinter
+        .finish()
+        .map_err(|err| err.to_string())?;
+    print_result(
+        &chunk.fingerprinter,
+        options,
+        first,
+        None,
+        input
+            .duration
+            .unwrap_or(processed_frames as f64 / sample_rate),
+        out,
+    )
+}
+
+fn print_chunk(
+    chunk: &mut Chunk,
+    options: &Options,
+    first: bool,
+    sample_rate: f64,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    chunk
+        .fingerprinter
+        .finish()
+        .map_err(|err| err.to_string())?;
+
+    print_result(
+        &chunk.fingerprinter,
+        options,
+        first,
+        Some(chunk.start as f64 / sample_rate),
+        chunk.frames as f64 / sample_rate,
+        out,
+    )
+}
+
+fn print_result(
+    fingerprinter: &Fingerprinter,
+    options: &Options,
+    first: bool,
+    timestamp: Option<f64>,
+    duration: f64,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let fingerprint = fingerprinter.fingerprint();
+    if fingerprint.0.is_empty() {
+        if first {
+            return Err("Empty fingerprint".to_owned());
+        }
+
+        return Ok(());
+    }
+
+    let encoded = if options.raw {
+        let items: Vec<String> = fingerprint
+            .0
+            .iter()
+            .map(|&item| {
+                if options.signed {
+                    (item as i32).to_string()
+                } else {
+                    item.to_string()
+                }
+            })
+            .collect();
+
+        items.join(",")
+    } else {
+        fingerprint
+            .compress()
+            .map_err(|err| err.to_string())?
+            .encode()
+    };
+
+    write_result(&encoded, options, first, timestamp, duration, out).map_err(|err| err.to_string())
+}
+
+fn write_result(
+    encoded: &str,
+    options: &Options,
+    first: bool,
+    timestamp: Option<f64>,
+    duration: f64,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    match options.format {
+        Format::Text => {
+            if !first {
+                writeln!(out)?;
+            }
+
+            match timestamp {
+                Some(timestamp) => {
+                    writeln!(out, "TIMESTAMP={:.2}", timestamp)?;
+                    writeln!(out, "DURATION={:.2}", duration)?;
+                }
+                None => writeln!(out, "DURATION={}", duration as u64)?,
+            }
+            writeln!(out, "FINGERPRINT={}", encoded)
+        }
+        Format::Json => {
+            let encoded = if options.raw {
+                format!("[{}]", encoded)
+            } else {
+                format!("\"{}\"", encoded)
+            };
+
+            match timestamp {
+                Some(timestamp) => writeln!(
+                    out,
+                    "{{\"timestamp\": {:.2}, \"duration\": {:.2}, \"fingerprint\": {}}}",
+                    timestamp, duration, encoded
+                ),
+                None => writeln!(
+                    out,
+                    "{{\"duration\": {:.2}, \"fingerprint\": {}}}",
+                    duration, encoded
+                ),
+            }
+        }
+        Format::Plain => writeln!(out, "{}", encoded),
+    }
+}
+
+fn main() {
+    let options = match parse_arguments(env::args().skip(1)) {
+        Ok(Command::Fingerprint(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("fpcalc version {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(err) => {
+            eprintln!("ERROR: {}", err);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut failed = false;
+    for (idx, path) in options.files.iter().enumerate() {
+        if options.format == Format::Text && options.files.len() > 1 {
+            if idx > 0 {
+                let _ = writeln!(out);
+            }
+            let _ = writeln!(out, "FILE={}", path);
+        }
+
+        if let Err(err) = process_file(path, &options, &mut out) {
+            eprintln!("ERROR: {}: {}", path, err);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_arguments, process_file, process_input, Command, Format, Input, Options};
+    use super::{Source, DEFAULT_SAMPLE_RATE};
+    use chromaprint::{Algorithm, Fingerprinter};
+    use std::fs;
+    use std::io::{self, Cursor, Read};
+
+    const TEST_FILE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_data/test_stereo_44100.raw"
+    );
+
+    fn parse(arguments: &[&str]) -> Result<Command, String> {
+        parse_arguments(arguments.iter().map(|argument| argument.to_string()))
+    }
+
+    fn parse_options(arguments: &[&str]) -> Options {
+        match parse(arguments) {
+            Ok(Command::Fingerprint(options)) => options,
+            _ => panic!("expected options for {:?}", arguments),
+        }
+    }
+
+    /// Fingerprints the file as mono, which gives fingerprints long enough to split in chunks.
+    fn run(arguments: &[&str]) -> String {
+        let mut arguments = arguments.to_vec();
+        arguments.extend_from_slice(&["-channels", "1", TEST_FILE]);
+        let options = parse_options(&arguments);
+
+        let mut out = Vec::new();
+        process_file(TEST_FILE, &options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn load_samples() -> Vec<i16> {
+        fs::read(TEST_FILE)
+            .unwrap()
+            .chunks(2)
+            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+            .collect()
+    }
+
+    fn encoded_fingerprint(samples: &[i16]) -> String {
+        let mut fingerprinter = Fingerprinter::new(44100, 1).unwrap();
+        fingerprinter.feed(samples).unwrap();
+        fingerprinter.finish().unwrap();
+        fingerprinter.fingerprint().compress().unwrap().encode()
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let options = parse_options(&["a.wav", "-", "b.wav"]);
+        assert_eq!(44100, options.sample_rate);
+        assert_eq!(2, options.channels);
+        assert_eq!(120.0, options.max_duration);
+        assert_eq!(0.0, options.chunk_duration);
+        assert!(!options.overlap);
+        assert_eq!(Algorithm::Test2, options.algorithm);
+        assert!(!options.raw);
+        assert!(!options.signed);
+        assert!(options.format == Format::Text);
+        assert_eq!(vec!["a.wav", "-", "b.wav"], options.files);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(&[
+            "-rate",
+            "8000",
+            "-channels",
+            "1",
+            "-length",
+            "30",
+            "-chunk",
+            "10",
+            "-overlap",
+            "-algorithm",
+            "4",
+            "-raw",
+            "-signed",
+            "-json",
+            "a.wav",
+        ]);
+        assert_eq!(8000, options.sample_rate);
+        assert_eq!(1, options.channels);
+        assert_eq!(30.0, options.max_duration);
+        assert_eq!(10.0, options.chunk_duration);
+        assert!(options.overlap);
+        assert_eq!(Algorithm::Test4, options.algorithm);
+        assert!(options.raw);
+        assert!(options.signed);
+        assert!(options.format == Format::Json);
+
+        assert!(parse_options(&["-json", "-plain", "a.wav"]).format == Format::Plain);
+        assert!(parse_options(&["--json", "--text", "a.wav"]).format == Format::Text);
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        // fpcalc numbers the algorithms from 1.
+        assert_eq!(
+            Algorithm::Test1,
+            parse_options(&["-algorithm", "1", "a.wav"]).algorithm
+        );
+        assert_eq!(
+            Algorithm::Test5,
+            parse_options(&["-algorithm", "5", "a.wav"]).algorithm
+        );
+
+        assert_eq!(
+            Some("Invalid value for -algorithm: 0".to_owned()),
+            parse(&["-algorithm", "0", "a.wav"]).err()
+        );
+        assert_eq!(
+            Some("Invalid value for -algorithm: 6".to_owned()),
+            parse(&["-algorithm", "6", "a.wav"]).err()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Some("No input files".to_owned()), parse(&[]).err());
+        assert_eq!(
+            Some("Unknown option: -foo".to_owned()),
+            parse(&["-foo", "a.wav"]).err()
+        );
+        assert_eq!(
+            Some("Missing value for -rate".to_owned()),
+            parse(&["-rate"]).err()
+        );
+        assert_eq!(
+            Some("Invalid value for -channels: two".to_owned()),
+            parse(&["-channels", "two", "a.wav"]).err()
+        );
+        assert_eq!(
+            Some("Invalid value for -length: -1".to_owned()),
+            parse(&["-length", "-1", "a.wav"]).err()
+        );
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert!(matches!(parse(&["-version"]), Ok(Command::Version)));
+        assert!(matches!(parse(&["a.wav", "-v"]), Ok(Command::Version)));
+        assert!(matches!(parse(&["-h"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["--help", "a.wav"]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn test_output() {
+        assert_eq!(
+            "DURATION=4\nFINGERPRINT=AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog\n",
+            run(&[])
+        );
+        assert_eq!(
+            "{\"duration\": 4.00, \"fingerprint\": \"AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog\"}\n",
+            run(&["-json"])
+        );
+        assert_eq!(
+            "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog\n",
+            run(&["-plain"])
+        );
+    }
+
+    #[test]
+    fn test_endless_stream() {
+        let options = parse_options(&["-length", "3", "-channels", "1", "-"]);
+        let samples = load_samples();
+
+        // Reads past the end of the file would never finish.
+        let stream = Cursor::new(fs::read(TEST_FILE).unwrap()).chain(io::repeat(1));
+        let input = Input {
+            source: Source::Raw(Box::new(stream), Vec::new()),
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            channels: 1,
+            duration: None,
+        };
+
+        let mut out = Vec::new();
+        process_input(input, &options, &mut out).unwrap();
+        assert_eq!(
+            format!(
+                "DURATION=3\nFINGERPRINT={}\n",
+                encoded_fingerprint(&samples[..(3 * 44100)])
+            ),
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_chunks() {
+        let samples = load_samples();
+
+        // The last second is too short for a fingerprint of its own, so it is left out.
+        assert_eq!(
+            format!(
+                "TIMESTAMP=0.00\nDURATION=3.00\nFINGERPRINT={}\n",
+                encoded_fingerprint(&samples[..(3 * 44100)])
+            ),
+            run(&["-chunk", "3"])
+        );
+    }
+
+    #[test]
+    fn test_overlapping_chunks() {
+        let samples = load_samples();
+        let start = 3 * 44100 - (Algorithm::default().delay() * 44100.0) as usize;
+
+        assert_eq!(
+            format!(
+                "TIMESTAMP=0.00\nDURATION=3.00\nFINGERPRINT={}\n\nTIMESTAMP={:.2}\nDURATION={:.2}\nFINGERPRINT={}\n",
+                encoded_fingerprint(&samples[..(3 * 44100)]),
+                start as f64 / 44100.0,
+                (samples.len() - start) as f64 / 44100.0,
+                encoded_fingerprint(&samples[start..])
+            ),
+            run(&["-chunk", "3", "-overlap"])
+        );
+    }
+}
@@ -0,0 +1,76 @@
+pub struct BitReader<'a> {
+    /// Input from which stuff will be read.
+    input: &'a [u8],
+
+    /// Index of the next byte which will be read from `input`.
+    input_index: usize,
+
+    /// Bits which were read from `input` but weren't returned yet.
+    buffer: u16,
+
+    /// A number between 0 and 16 indicating how many bits of `buffer` are full.
+    buffer_size: u8,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(input: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            input,
+            input_index: 0,
+            buffer: 0,
+            buffer_size: 0,
+        }
+    }
+
+    /// Reads a `bits` number of bits from the stream. Returns `None` once the input runs out.
+    pub fn read(&mut self, bits: u8) -> Option<u8> {
+        while self.buffer_size < bits {
+            let byte = *self.input.get(self.input_index)?;
+            self.buffer |= (byte as u16) << self.buffer_size;
+            self.buffer_size += 8;
+            self.input_index += 1;
+        }
+
+        let value = (self.buffer & ((1 << bits) - 1)) as u8;
+        self.buffer >>= bits;
+        self.buffer_size -= bits;
+
+        Some(value)
+    }
+
+    /// The number of bytes of input which were read, including partially read bytes.
+    pub fn bytes_read(&self) -> usize {
+        self.input_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitReader;
+    use bit_writer::BitWriter;
+
+    #[test]
+    fn test_read() {
+        let mut reader = BitReader::new(&[73, 0]);
+
+        assert_eq!(Some(1), reader.read(3));
+        assert_eq!(Some(1), reader.read(3));
+        assert_eq!(Some(1), reader.read(3));
+        assert_eq!(2, reader.bytes_read());
+        assert_eq!(Some(0), reader.read(3));
+        assert_eq!(Some(0), reader.read(3));
+        assert_eq!(None, reader.read(3));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let values = [0u8, 31, 7, 12, 1, 30, 16];
+        let mut output = Vec::new();
+        BitWriter::write_all_into(&values, 5, &mut output);
+
+        let mut reader = BitReader::new(&output);
+        for value in values.iter() {
+            assert_eq!(Some(*value), reader.read(5));
+        }
+    }
+}
@@ -1,9 +1,8 @@
+use alloc::vec::Vec;
+
 pub struct BitWriter<'a> {
     /// Output to which stuff will be written.
-    output: &'a mut [u8],
-
-    /// Index at which the next byte will be written to in `output`.
-    output_index: usize,
+    output: &'a mut Vec<u8>,
 
     /// A staging area for bits to be written to before they are written to `output`.
     buffer: u16,
@@ -13,19 +12,16 @@ pub struct BitWriter<'a> {
 }
 
 impl<'a> BitWriter<'a> {
-    pub fn new(output: &'a mut [u8]) -> BitWriter<'a> {
+    pub fn new(output: &'a mut Vec<u8>) -> BitWriter<'a> {
         BitWriter {
             output,
-            output_index: 0,
             buffer: 0,
             buffer_size: 0,
         }
     }
 
     pub fn write_all(&mut self, values: &[u8], bits: u8) {
-        values
-            .into_iter()
-            .for_each(|value| self.write(*value, bits));
+        values.iter().for_each(|value| self.write(*value, bits));
     }
 
     /// Writes a `bits` number of bits from `value` to the stream.
@@ -38,24 +34,23 @@ impl<'a> BitWriter<'a> {
         }
     }
 
-    /// Writes the buffered bits into the output. Returns the amount of space used in the output.
-    pub fn flush(&mut self) -> usize {
+    /// Writes the buffered bits into the output, padding the last byte with zeros.
+    pub fn flush(&mut self) {
         while self.buffer_size > 0 {
             self.write_buffer_to_output();
         }
 
         self.buffer_size = 0;
-        self.output_index
     }
 
     fn write_buffer_to_output(&mut self) {
-        self.output[self.output_index] = (self.buffer & 255 as u16) as u8;
-        self.output_index += 1;
+        self.output.push((self.buffer & 255) as u8);
         self.buffer >>= 8;
         self.buffer_size = u8::saturating_sub(self.buffer_size, 8);
     }
 
-    pub fn write_all_into(input: &[u8], bits: u8, output: &mut [u8]) -> usize {
+    /// Appends `input` to `output` with `bits` bits per value.
+    pub fn write_all_into(input: &[u8], bits: u8, output: &mut Vec<u8>) {
         let mut writer = BitWriter::new(output);
         writer.write_all(input, bits);
         writer.flush()
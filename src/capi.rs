@@ -0,0 +1,552 @@
+//! A C interface compatible with the one in `chromaprint.h` from libchromaprint.
+//!
+//! Functions return 1 on success and 0 on failure. Memory returned through out parameters is
+//! allocated with `malloc` and must be released with `chromaprint_dealloc`.
+
+use std::mem;
+use std::ptr;
+use std::slice;
+
+use libc::{c_char, c_int, c_void};
+
+use algorithm::Algorithm;
+use encode;
+use fingerprint_compressor;
+use fingerprint_decompressor;
+use fingerprinter::Fingerprinter;
+
+pub const CHROMAPRINT_ALGORITHM_TEST1: c_int = 0;
+pub const CHROMAPRINT_ALGORITHM_TEST2: c_int = 1;
+pub const CHROMAPRINT_ALGORITHM_TEST3: c_int = 2;
+pub const CHROMAPRINT_ALGORITHM_TEST4: c_int = 3;
+pub const CHROMAPRINT_ALGORITHM_TEST5: c_int = 4;
+pub const CHROMAPRINT_ALGORITHM_DEFAULT: c_int = CHROMAPRINT_ALGORITHM_TEST2;
+
+/// A fingerprinter which is reset for each stream by `chromaprint_start`.
+pub struct ChromaprintContext {
+    algorithm: Algorithm,
+    fingerprinter: Option<Fingerprinter>,
+}
+
+/// The version of this library.
+#[no_mangle]
+pub extern "C" fn chromaprint_get_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Allocates a context which fingerprints with `algorithm`.
+///
+/// # Returns
+/// A null pointer when `algorithm` isn't known.
+#[no_mangle]
+pub extern "C" fn chromaprint_new(algorithm: c_int) -> *mut ChromaprintContext {
+    match to_algorithm(algorithm) {
+        Some(algorithm) => Box::into_raw(Box::new(ChromaprintContext {
+            algorithm,
+            fingerprinter: None,
+        })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Releases a context allocated by `chromaprint_new`.
+///
+/// # Safety
+/// `ctx` must be null or a context returned by `chromaprint_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_free(ctx: *mut ChromaprintContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// # Safety
+/// `ctx` must be null or a live context.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_get_algorithm(ctx: *mut ChromaprintContext) -> c_int {
+    match ctx.as_ref() {
+        Some(ctx) => ctx.algorithm.id() as c_int,
+        None => -1,
+    }
+}
+
+/// Starts fingerprinting a new stream of interleaved audio, discarding the previous
+/// fingerprint.
+///
+/// # Safety
+/// `ctx` must be null or a live context.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_start(
+    ctx: *mut ChromaprintContext,
+    sample_rate: c_int,
+    num_channels: c_int,
+) -> c_int {
+    let ctx = match ctx.as_mut() {
+        Some(ctx) => ctx,
+        None => return 0,
+    };
+    if sample_rate <= 0 || num_channels <= 0 || num_channels > c_int::from(u16::MAX) {
+        return 0;
+    }
+
+    ctx.fingerprinter =
+        Fingerprinter::with_algorithm(sample_rate as u32, num_channels as u16, ctx.algorithm).ok();
+
+    ctx.fingerprinter.is_some() as c_int
+}
+
+/// Feeds `size` interleaved samples, counting the samples of every channel.
+///
+/// # Safety
+/// `ctx` must be null or a live context and `data` must point to `size` samples.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_feed(
+    ctx: *mut ChromaprintContext,
+    data: *const i16,
+    size: c_int,
+) -> c_int {
+    let fingerprinter = match ctx.as_mut().and_then(|ctx| ctx.fingerprinter.as_mut()) {
+        Some(fingerprinter) => fingerprinter,
+        None => return 0,
+    };
+    let samples = match to_slice(data, size) {
+        Some(samples) => samples,
+        None => return 0,
+    };
+
+    fingerprinter.feed(samples).is_ok() as c_int
+}
+
+/// Processes any buffered audio once the whole stream has been fed.
+///
+/// # Safety
+/// `ctx` must be null or a live context.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_finish(ctx: *mut ChromaprintContext) -> c_int {
+    match ctx.as_mut().and_then(|ctx| ctx.fingerprinter.as_mut()) {
+        Some(fingerprinter) => fingerprinter.finish().is_ok() as c_int,
+        None => 0,
+    }
+}
+
+/// Returns the compressed fingerprint encoded in base64 as a null terminated string.
+///
+/// # Safety
+/// `ctx` must be null or a live context and `fingerprint` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_get_fingerprint(
+    ctx: *mut ChromaprintContext,
+    fingerprint: *mut *mut c_char,
+) -> c_int {
+    let fingerprinter = match ctx.as_ref().and_then(|ctx| ctx.fingerprinter.as_ref()) {
+        Some(fingerprinter) => fingerprinter,
+        None => return 0,
+    };
+    let compressed = match fingerprinter.fingerprint().compress() {
+        Ok(compressed) => compressed,
+        Err(_) => return 0,
+    };
+
+    write_string(compressed.encode().as_bytes(), fingerprint, ptr::null_mut())
+}
+
+/// Returns the raw sub-fingerprints computed so far.
+///
+/// # Safety
+/// `ctx` must be null or a live context and `fingerprint` and `size` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_get_raw_fingerprint(
+    ctx: *mut ChromaprintContext,
+    fingerprint: *mut *mut u32,
+    size: *mut c_int,
+) -> c_int {
+    match ctx.as_ref().and_then(|ctx| ctx.fingerprinter.as_ref()) {
+        Some(fingerprinter) => write_items(fingerprinter.fingerprint().0, fingerprint, size),
+        None => 0,
+    }
+}
+
+/// # Safety
+/// `ctx` must be null or a live context and `size` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_get_raw_fingerprint_size(
+    ctx: *mut ChromaprintContext,
+    size: *mut c_int,
+) -> c_int {
+    let fingerprinter = match ctx.as_ref().and_then(|ctx| ctx.fingerprinter.as_ref()) {
+        Some(fingerprinter) => fingerprinter,
+        None => return 0,
+    };
+    if size.is_null() {
+        return 0;
+    }
+
+    *size = fingerprinter.fingerprint().0.len() as c_int;
+
+    1
+}
+
+/// Computes `chromaprint_hash_fingerprint` for the fingerprint computed so far.
+///
+/// # Safety
+/// `ctx` must be null or a live context and `hash` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_get_fingerprint_hash(
+    ctx: *mut ChromaprintContext,
+    hash: *mut u32,
+) -> c_int {
+    let fingerprinter = match ctx.as_ref().and_then(|ctx| ctx.fingerprinter.as_ref()) {
+        Some(fingerprinter) => fingerprinter,
+        None => return 0,
+    };
+    if hash.is_null() {
+        return 0;
+    }
+
+    *hash = simhash(fingerprinter.fingerprint().0);
+
+    1
+}
+
+/// Compresses raw sub-fingerprints computed with `algorithm`, encoding them in base64 when
+/// `base64` is non-zero. The result is null terminated, and its length excluding the terminator
+/// is written to `encoded_size`.
+///
+/// # Safety
+/// `fp` must point to `size` items and the out parameters must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_encode_fingerprint(
+    fp: *const u32,
+    size: c_int,
+    algorithm: c_int,
+    encoded_fp: *mut *mut c_char,
+    encoded_size: *mut c_int,
+    base64: c_int,
+) -> c_int {
+    let fingerprint = match to_slice(fp, size) {
+        Some(fingerprint) => fingerprint,
+        None => return 0,
+    };
+    let algorithm = match to_algorithm(algorithm) {
+        Some(algorithm) => algorithm,
+        None => return 0,
+    };
+    let compressed = match fingerprint_compressor::compress(fingerprint, algorithm.id()) {
+        Ok(compressed) => compressed,
+        Err(_) => return 0,
+    };
+
+    if base64 != 0 {
+        write_string(
+            encode::encode(&compressed).as_bytes(),
+            encoded_fp,
+            encoded_size,
+        )
+    } else {
+        write_string(&compressed, encoded_fp, encoded_size)
+    }
+}
+
+/// Reverses `chromaprint_encode_fingerprint`, writing the id of the algorithm to `algorithm`.
+///
+/// # Safety
+/// `encoded_fp` must point to `encoded_size` bytes and the out parameters must be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_decode_fingerprint(
+    encoded_fp: *const c_char,
+    encoded_size: c_int,
+    fp: *mut *mut u32,
+    size: *mut c_int,
+    algorithm: *mut c_int,
+    base64: c_int,
+) -> c_int {
+    let encoded = match to_slice(encoded_fp as *const u8, encoded_size) {
+        Some(encoded) => encoded,
+        None => return 0,
+    };
+
+    let decoded;
+    let compressed = if base64 != 0 {
+        decoded = match String::from_utf8(encoded.to_vec())
+            .ok()
+            .and_then(|encoded| encode::decode(&encoded).ok())
+        {
+            Some(decoded) => decoded,
+            None => return 0,
+        };
+        &decoded[..]
+    } else {
+        encoded
+    };
+
+    let (fingerprint, decoded_algorithm) = match fingerprint_decompressor::decompress(compressed) {
+        Ok(decompressed) => decompressed,
+        Err(_) => return 0,
+    };
+    if !algorithm.is_null() {
+        *algorithm = decoded_algorithm.id() as c_int;
+    }
+
+    write_items(&fingerprint, fp, size)
+}
+
+/// Hashes raw sub-fingerprints into 32 bits, so that similar fingerprints have hashes which
+/// differ in few bits.
+///
+/// # Safety
+/// `fp` must point to `size` items and `hash` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_hash_fingerprint(
+    fp: *const u32,
+    size: c_int,
+    hash: *mut u32,
+) -> c_int {
+    match to_slice(fp, size) {
+        Some(fingerprint) if !hash.is_null() => {
+            *hash = simhash(fingerprint);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Frees memory returned by the other functions.
+///
+/// # Safety
+/// `ptr` must be null or memory returned by this library which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chromaprint_dealloc(ptr: *mut c_void) {
+    libc::free(ptr);
+}
+
+/// Sets each bit of the hash when it is set in more than half of the items.
+fn simhash(fingerprint: &[u32]) -> u32 {
+    let mut counts = [0i64; 32];
+    for item in fingerprint {
+        for (bit, count) in counts.iter_mut().enumerate() {
+            if item & (1 << bit) != 0 {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+fn to_algorithm(algorithm: c_int) -> Option<Algorithm> {
+    if algorithm < 0 || algorithm > c_int::from(u8::MAX) {
+        return None;
+    }
+
+    Algorithm::from_id(algorithm as u8)
+}
+
+unsafe fn to_slice<'a, T>(data: *const T, size: c_int) -> Option<&'a [T]> {
+    if size < 0 || (data.is_null() && size > 0) {
+        return None;
+    }
+    if size == 0 {
+        return Some(&[]);
+    }
+
+    Some(slice::from_raw_parts(data, size as usize))
+}
+
+/// Copies `items` into memory from `malloc`.
+unsafe fn write_items(items: &[u32], output: *mut *mut u32, size: *mut c_int) -> c_int {
+    if output.is_null() || size.is_null() || items.len() > c_int::MAX as usize {
+        return 0;
+    }
+
+    let buffer = libc::malloc(mem::size_of_val(items).max(1)) as *mut u32;
+    if buffer.is_null() {
+        return 0;
+    }
+    ptr::copy_nonoverlapping(items.as_ptr(), buffer, items.len());
+
+    *output = buffer;
+    *size = items.len() as c_int;
+
+    1
+}
+
+/// Copies `bytes` into memory from `malloc` followed by a null terminator. `size` may be null.
+unsafe fn write_string(bytes: &[u8], output: *mut *mut c_char, size: *mut c_int) -> c_int {
+    if output.is_null() || bytes.len() > c_int::MAX as usize {
+        return 0;
+    }
+
+    let buffer = libc::malloc(bytes.len() + 1) as *mut u8;
+    if buffer.is_null() {
+        return 0;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+    *buffer.add(bytes.len()) = 0;
+
+    *output = buffer as *mut c_char;
+    if !size.is_null() {
+        *size = bytes.len() as c_int;
+    }
+
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::ffi::CStr;
+    use std::path::PathBuf;
+    use tests;
+
+    unsafe fn fingerprint_test_file(ctx: *mut ChromaprintContext) {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap();
+
+        assert_eq!(1, chromaprint_start(ctx, 44100, 1));
+        for chunk in samples.chunks(1000) {
+            assert_eq!(
+                1,
+                chromaprint_feed(ctx, chunk.as_ptr(), chunk.len() as c_int)
+            );
+        }
+        assert_eq!(1, chromaprint_finish(ctx));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        unsafe {
+            let ctx = chromaprint_new(CHROMAPRINT_ALGORITHM_DEFAULT);
+            fingerprint_test_file(ctx);
+
+            let mut fingerprint = ptr::null_mut();
+            assert_eq!(1, chromaprint_get_fingerprint(ctx, &mut fingerprint));
+            assert_eq!(
+                "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog",
+                CStr::from_ptr(fingerprint).to_str().unwrap()
+            );
+
+            let mut raw = ptr::null_mut();
+            let mut size = 0;
+            assert_eq!(1, chromaprint_get_raw_fingerprint(ctx, &mut raw, &mut size));
+
+            let mut encoded = ptr::null_mut();
+            let mut encoded_size = 0;
+            assert_eq!(
+                1,
+                chromaprint_encode_fingerprint(
+                    raw,
+                    size,
+                    CHROMAPRINT_ALGORITHM_DEFAULT,
+                    &mut encoded,
+                    &mut encoded_size,
+                    1
+                )
+            );
+            assert_eq!(CStr::from_ptr(fingerprint), CStr::from_ptr(encoded));
+
+            let mut decoded = ptr::null_mut();
+            let mut decoded_size = 0;
+            let mut algorithm = -1;
+            assert_eq!(
+                1,
+                chromaprint_decode_fingerprint(
+                    encoded,
+                    encoded_size,
+                    &mut decoded,
+                    &mut decoded_size,
+                    &mut algorithm,
+                    1
+                )
+            );
+            assert_eq!(CHROMAPRINT_ALGORITHM_DEFAULT, algorithm);
+            assert_eq!(
+                slice::from_raw_parts(raw, size as usize),
+                slice::from_raw_parts(decoded, decoded_size as usize)
+            );
+
+            let mut hash = 0;
+            let mut context_hash = 0;
+            assert_eq!(1, chromaprint_hash_fingerprint(raw, size, &mut hash));
+            assert_eq!(1, chromaprint_get_fingerprint_hash(ctx, &mut context_hash));
+            assert_eq!(hash, context_hash);
+
+            for ptr in &[
+                fingerprint as *mut c_void,
+                raw as *mut c_void,
+                encoded as *mut c_void,
+                decoded as *mut c_void,
+            ] {
+                chromaprint_dealloc(*ptr);
+            }
+            chromaprint_free(ctx);
+        }
+    }
+
+    #[test]
+    fn test_restart() {
+        unsafe {
+            let ctx = chromaprint_new(CHROMAPRINT_ALGORITHM_TEST4);
+            assert_eq!(CHROMAPRINT_ALGORITHM_TEST4, chromaprint_get_algorithm(ctx));
+
+            let mut first = 0;
+            let mut second = 0;
+            fingerprint_test_file(ctx);
+            assert_eq!(1, chromaprint_get_raw_fingerprint_size(ctx, &mut first));
+            assert_eq!(0, chromaprint_finish(ctx));
+            fingerprint_test_file(ctx);
+            assert_eq!(1, chromaprint_get_raw_fingerprint_size(ctx, &mut second));
+
+            assert!(first > 0);
+            assert_eq!(first, second);
+
+            chromaprint_free(ctx);
+        }
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        unsafe {
+            assert!(chromaprint_new(5).is_null());
+
+            let ctx = chromaprint_new(CHROMAPRINT_ALGORITHM_DEFAULT);
+            let mut size = 0;
+            assert_eq!(0, chromaprint_feed(ctx, [0i16; 4].as_ptr(), 4));
+            assert_eq!(0, chromaprint_get_raw_fingerprint_size(ctx, &mut size));
+            assert_eq!(0, chromaprint_start(ctx, 0, 2));
+            assert_eq!(1, chromaprint_start(ctx, 44100, 2));
+            assert_eq!(0, chromaprint_feed(ctx, ptr::null(), 4));
+            assert_eq!(0, chromaprint_feed(ctx, [0i16; 4].as_ptr(), -1));
+            chromaprint_free(ctx);
+
+            let mut fp = ptr::null_mut();
+            let mut algorithm = 0;
+            assert_eq!(
+                0,
+                chromaprint_decode_fingerprint(
+                    b"!!".as_ptr() as *const c_char,
+                    2,
+                    &mut fp,
+                    &mut size,
+                    &mut algorithm,
+                    1
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_simhash() {
+        assert_eq!(0, simhash(&[]));
+        assert_eq!(0b0101, simhash(&[0b0111, 0b0101, 0b1101]));
+    }
+}
@@ -1,11 +1,25 @@
+use alloc::vec::Vec;
+use math;
+use shared::Shared;
+
+#[derive(Clone)]
 pub struct Chroma {
-    note_range: NoteRange,
+    /// Shared between clones, as it only depends on the frame size and sample rate.
+    note_range: Shared<NoteRange>,
+    interpolate: bool,
 }
 
 impl Chroma {
-    pub fn new(min_freq: u32, max_freq: u32, frame_size: u32, sample_rate: u32) -> Chroma {
+    pub fn new(
+        min_freq: u32,
+        max_freq: u32,
+        frame_size: u32,
+        sample_rate: u32,
+        interpolate: bool,
+    ) -> Chroma {
         Chroma {
-            note_range: NoteRange::new(min_freq, max_freq, frame_size, sample_rate),
+            note_range: Shared::new(NoteRange::new(min_freq, max_freq, frame_size, sample_rate)),
+            interpolate,
         }
     }
 
@@ -14,7 +28,28 @@ impl Chroma {
         let note_for_idx = self.note_range.notes();
 
         for idx in self.note_range.min_idx..self.note_range.max_idx {
-            notes[note_for_idx[idx as usize] as usize] += frame[idx as usize];
+            let idx = idx as usize;
+            let note = note_for_idx[idx] as usize;
+            let energy = frame[idx];
+
+            if !self.interpolate {
+                notes[note] += energy;
+                continue;
+            }
+
+            // Splits the energy between this note and the closest neighbouring note depending on
+            // how far the frequency is from the center of the note.
+            let frac = self.note_range.notes_frac[idx];
+            let (other_note, a) = if frac < 0.5 {
+                ((note + 11) % 12, 0.5 + frac)
+            } else if frac > 0.5 {
+                ((note + 1) % 12, 1.5 - frac)
+            } else {
+                (note, 1.0)
+            };
+
+            notes[note] += energy * a;
+            notes[other_note] += energy * (1.0 - a);
         }
 
         notes
@@ -26,6 +61,9 @@ struct NoteRange {
     max_idx: u32,
 
     notes: Vec<u8>,
+
+    /// The fractional part of the note for each index, used when interpolating.
+    notes_frac: Vec<f64>,
 }
 
 impl NoteRange {
@@ -36,18 +74,21 @@ impl NoteRange {
             freq_to_idx(max_freq, frame_size, sample_rate),
         );
         let mut notes = vec![0u8; frame_size as usize];
+        let mut notes_frac = vec![0f64; frame_size as usize];
 
         for idx in min_idx..max_idx {
             let freq = idx_to_freq(idx, frame_size, sample_rate);
-            let note = note_from_freq(freq);
+            let note = fractional_note_from_freq(freq);
 
-            notes[idx as usize] = note
+            notes[idx as usize] = note as u8;
+            notes_frac[idx as usize] = note - math::floor(note);
         }
 
         NoteRange {
             min_idx,
             max_idx,
             notes,
+            notes_frac,
         }
     }
 
@@ -64,7 +105,7 @@ impl NoteRange {
 /// * `sample_rate` - The maximum frequency.
 fn freq_to_idx(freq: u32, frame_size: u32, sample_rate: u32) -> u32 {
     let size_per_frequency = (frame_size as f32) / (sample_rate as f32);
-    return (freq as f32 * size_per_frequency).round() as u32;
+    return math::roundf(freq as f32 * size_per_frequency) as u32;
 }
 
 /// Converts an index in an FFT array to a frequency.
@@ -81,10 +122,10 @@ fn idx_to_freq(idx: u32, frame_size: u32, sample_rate: u32) -> f64 {
 /// Converts a frequency in Hz into a note.
 ///
 /// # Returns
-/// A value between 0 and 11. 0 corresponds to A and 11 to GSharp.
-fn note_from_freq(frequency: f64) -> u8 {
-    let octave = ((frequency as f64) / (440f64 / 16f64)).log2();
-    (12f64 * (octave - octave.floor())) as u8
+/// A value in `[0, 12)`. 0 corresponds to A and 11 to GSharp.
+fn fractional_note_from_freq(frequency: f64) -> f64 {
+    let octave = math::log2((frequency as f64) / (440f64 / 16f64));
+    12f64 * (octave - math::floor(octave))
 }
 
 #[cfg(test)]
@@ -100,7 +141,7 @@ mod tests {
 
     #[test]
     fn chroma_normal_a() {
-        let chroma = Chroma::new(10, 510, 256, 1000);
+        let chroma = Chroma::new(10, 510, 256, 1000, false);
         let mut frame = [0.0f64; 128];
         frame[113] = 1.0;
 
@@ -112,7 +153,7 @@ mod tests {
 
     #[test]
     fn chroma_normal_g_sharp() {
-        let chroma = Chroma::new(10, 510, 256, 1000);
+        let chroma = Chroma::new(10, 510, 256, 1000, false);
         let mut frame = [0.0f64; 128];
         frame[112] = 1.0;
 
@@ -124,7 +165,7 @@ mod tests {
 
     #[test]
     fn chroma_normal_b() {
-        let chroma = Chroma::new(10, 510, 256, 1000);
+        let chroma = Chroma::new(10, 510, 256, 1000, false);
         let mut frame = [0.0f64; 128];
         frame[64] = 1.0;
 
@@ -134,6 +175,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn chroma_interpolated_a() {
+        let chroma = Chroma::new(10, 510, 256, 1000, true);
+        let mut frame = [0.0f64; 128];
+        frame[113] = 1.0;
+
+        let features = chroma.handle_frame(&frame);
+        assert_ulps_eq!(1.0, features.iter().sum::<f64>());
+        assert!(features[0] > 0.5);
+        assert!(features[0] < 1.0);
+        assert_ulps_eq!(1.0 - features[0], features[1] + features[11]);
+    }
+
     #[test]
     fn test_notes_freq() {
         const MIN_FREQ: u32 = 28;
@@ -154,7 +208,7 @@ mod tests {
         const TARGET_SAMPLE_RATE: u32 = 11025;
 
         let expected = test_data::get_chroma_features();
-        let chroma = Chroma::new(MIN_FREQ, MAX_FREQ, FRAME_SIZE, TARGET_SAMPLE_RATE);
+        let chroma = Chroma::new(MIN_FREQ, MAX_FREQ, FRAME_SIZE, TARGET_SAMPLE_RATE, false);
 
         let features: Vec<_> = fft_frames
             .into_iter()
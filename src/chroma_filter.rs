@@ -1,5 +1,6 @@
 pub const FILTER_COEFFICIENTS: [f64; 5] = [0.25, 0.75, 1.0, 0.75, 0.25];
 
+#[derive(Clone)]
 pub struct ChromaFilter {
     filter_coefficients: &'static [f64],
     buffer: [[f64; 12]; 8],
@@ -1,3 +1,5 @@
+use math;
+
 pub fn normalize_vector(mut vector: [f64; 12]) -> [f64; 12] {
     let norm = euclidean_norm(&vector);
     if norm < 0.01 {
@@ -18,7 +20,7 @@ pub fn euclidean_norm(vector: &[f64; 12]) -> f64 {
     }
 
     if squares > 0.0 {
-        squares.sqrt()
+        math::sqrt(squares)
     } else {
         0.0
     }
@@ -4,7 +4,76 @@ use quantizer::Quantizer;
 pub type Classifier = (Filter, Quantizer);
 pub type Classifiers = [Classifier; 16];
 
-pub fn get_default_classifier() -> Classifiers {
+pub fn get_test1_classifier() -> Classifiers {
+    [
+        (
+            Filter::new(0, 0, 3, 15),
+            Quantizer::new(2.10543, 2.45354, 2.69414),
+        ),
+        (
+            Filter::new(1, 0, 4, 14),
+            Quantizer::new(-0.345922, 0.0463746, 0.446251),
+        ),
+        (
+            Filter::new(1, 4, 4, 11),
+            Quantizer::new(-0.392132, 0.0291077, 0.443391),
+        ),
+        (
+            Filter::new(3, 0, 4, 14),
+            Quantizer::new(-0.192851, 0.00583535, 0.204053),
+        ),
+        (
+            Filter::new(2, 8, 2, 4),
+            Quantizer::new(-0.0771619, -0.00991999, 0.0575406),
+        ),
+        (
+            Filter::new(5, 6, 2, 15),
+            Quantizer::new(-0.710437, -0.518954, -0.330402),
+        ),
+        (
+            Filter::new(1, 9, 2, 16),
+            Quantizer::new(-0.353724, -0.0189719, 0.289768),
+        ),
+        (
+            Filter::new(3, 4, 2, 10),
+            Quantizer::new(-0.128418, -0.0285697, 0.0591791),
+        ),
+        (
+            Filter::new(3, 9, 2, 16),
+            Quantizer::new(-0.139052, -0.0228468, 0.0879723),
+        ),
+        (
+            Filter::new(2, 1, 3, 6),
+            Quantizer::new(-0.133562, 0.00669205, 0.155012),
+        ),
+        (
+            Filter::new(3, 3, 6, 2),
+            Quantizer::new(-0.0267, 0.00804829, 0.0459773),
+        ),
+        (
+            Filter::new(2, 8, 1, 10),
+            Quantizer::new(-0.0972417, 0.0152227, 0.129003),
+        ),
+        (
+            Filter::new(3, 4, 4, 14),
+            Quantizer::new(-0.141434, 0.00374515, 0.149935),
+        ),
+        (
+            Filter::new(5, 4, 2, 15),
+            Quantizer::new(-0.64035, -0.466999, -0.285493),
+        ),
+        (
+            Filter::new(5, 9, 2, 3),
+            Quantizer::new(-0.322792, -0.254258, -0.174278),
+        ),
+        (
+            Filter::new(2, 1, 8, 4),
+            Quantizer::new(-0.0741375, -0.00590933, 0.0600357),
+        ),
+    ]
+}
+
+pub fn get_test2_classifier() -> Classifiers {
     [
         (
             Filter::new(0, 4, 3, 15),
@@ -72,3 +141,9 @@ pub fn get_default_classifier() -> Classifiers {
         ),
     ]
 }
+
+/// The third test algorithm was trained with the same classifiers as the second one, it only
+/// differs in how chroma features are computed.
+pub fn get_test3_classifier() -> Classifiers {
+    get_test2_classifier()
+}
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 pub struct CombinedBuffer<'a, 'b, T>
 where
     T: 'a + 'b,
@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+use core::cmp;
+
+/// The number of most significant bits of each item used to find candidate offsets.
+const MATCH_BITS: u32 = 14;
+
+/// How closely two fingerprints match where they overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Similarity {
+    /// The number of items which overlap.
+    pub overlap: usize,
+
+    /// The number of bits which differ between the overlapping items.
+    pub bit_errors: u64,
+}
+
+impl Similarity {
+    /// The fraction of overlapping bits which differ, between 0 and 1.
+    pub fn bit_error_rate(&self) -> f64 {
+        self.bit_errors as f64 / (self.overlap as f64 * 32.0)
+    }
+
+    /// The fraction of overlapping bits which match, between 0 and 1.
+    pub fn score(&self) -> f64 {
+        1.0 - self.bit_error_rate()
+    }
+}
+
+/// Compares the overlapping items of two fingerprints when `b` starts `offset` items into `a`.
+/// Item `i` of `a` is compared with item `i - offset` of `b`, so a negative offset means `a`
+/// starts part way into `b`.
+///
+/// # Returns
+/// `None` when the fingerprints don't overlap at `offset`.
+pub fn compare(a: &[u32], b: &[u32], offset: isize) -> Option<Similarity> {
+    let (a, b) = if offset >= 0 {
+        (a.get(offset as usize..)?, b)
+    } else {
+        (a, b.get(offset.unsigned_abs()..)?)
+    };
+
+    let overlap = cmp::min(a.len(), b.len());
+    if overlap == 0 {
+        return None;
+    }
+
+    let bit_errors = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| (a ^ b).count_ones() as u64)
+        .sum();
+
+    Some(Similarity {
+        overlap,
+        bit_errors,
+    })
+}
+
+/// Finds the offset at which `b` best lines up with `a`, using the same convention as `compare`.
+///
+/// Items are reduced to their most significant bits and a histogram of the offsets between
+/// equal hashes in `a` and `b` is built, so each offset doesn't need to be compared bit by bit.
+/// Only offsets within `max_offset` items in either direction are considered when it is given.
+///
+/// # Returns
+/// `None` when no hashes are shared between the fingerprints.
+pub fn best_offset(a: &[u32], b: &[u32], max_offset: Option<usize>) -> Option<isize> {
+    let a_offsets = last_offsets(a);
+    let b_offsets = last_offsets(b);
+
+    let mut counts = vec![0u32; a.len() + b.len() + 1];
+    let mut top_count = 0;
+    let mut top_offset = None;
+
+    for (a_offset, b_offset) in a_offsets.iter().zip(b_offsets.iter()) {
+        let (a_offset, b_offset) = match (*a_offset, *b_offset) {
+            (Some(a_offset), Some(b_offset)) => (a_offset as isize, b_offset as isize),
+            _ => continue,
+        };
+
+        let offset = a_offset - b_offset;
+        if let Some(max_offset) = max_offset {
+            if offset.unsigned_abs() > max_offset {
+                continue;
+            }
+        }
+
+        let count = &mut counts[(offset + b.len() as isize) as usize];
+        *count += 1;
+        if *count > top_count {
+            top_count = *count;
+            top_offset = Some(offset);
+        }
+    }
+
+    top_offset
+}
+
+/// Maps each hash to the position of the last item with that hash.
+fn last_offsets(fingerprint: &[u32]) -> Vec<Option<usize>> {
+    let mut offsets = vec![None; 1 << MATCH_BITS];
+    for (idx, item) in fingerprint.iter().enumerate() {
+        offsets[(item >> (32 - MATCH_BITS)) as usize] = Some(idx);
+    }
+
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_offset, compare, Similarity};
+
+    fn random_fingerprint(length: usize, seed: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical() {
+        let a = [1, 2, 3, 0xffff_ffff];
+        let similarity = compare(&a, &a, 0).unwrap();
+
+        assert_eq!(
+            Similarity {
+                overlap: 4,
+                bit_errors: 0,
+            },
+            similarity
+        );
+        assert_eq!(1.0, similarity.score());
+    }
+
+    #[test]
+    fn test_bit_errors() {
+        let similarity = compare(&[0, 0], &[0xffff_ffff, 1], 0).unwrap();
+
+        assert_eq!(33, similarity.bit_errors);
+        assert_eq!(33.0 / 64.0, similarity.bit_error_rate());
+    }
+
+    #[test]
+    fn test_positive_offset() {
+        let a = [9, 9, 1, 2, 3];
+        let b = [1, 2, 3, 4, 5];
+
+        assert_eq!(
+            Some(Similarity {
+                overlap: 3,
+                bit_errors: 0,
+            }),
+            compare(&a, &b, 2)
+        );
+    }
+
+    #[test]
+    fn test_negative_offset() {
+        let a = [3, 4];
+        let b = [1, 2, 3, 4, 5];
+
+        assert_eq!(
+            Some(Similarity {
+                overlap: 2,
+                bit_errors: 0,
+            }),
+            compare(&a, &b, -2)
+        );
+    }
+
+    #[test]
+    fn test_no_overlap() {
+        let a = [1, 2, 3];
+        let b = [1, 2, 3];
+
+        assert_eq!(None, compare(&a, &b, 3));
+        assert_eq!(None, compare(&a, &b, -3));
+        assert_eq!(None, compare(&a, &b, 10));
+        assert_eq!(None, compare(&[], &b, 0));
+    }
+
+    #[test]
+    fn test_best_offset() {
+        let a = random_fingerprint(500, 1);
+        let mut b = a[37..237].to_vec();
+        for item in b.iter_mut().step_by(3) {
+            *item ^= 0x0000_0101;
+        }
+
+        assert_eq!(Some(37), best_offset(&a, &b, None));
+        assert_eq!(Some(-37), best_offset(&b, &a, None));
+    }
+
+    #[test]
+    fn test_best_offset_limited() {
+        let a = random_fingerprint(500, 1);
+        let b = a[200..300].to_vec();
+
+        assert_eq!(Some(200), best_offset(&a, &b, Some(200)));
+        assert_eq!(None, best_offset(&a, &b, Some(199)));
+    }
+
+    #[test]
+    fn test_best_offset_unrelated() {
+        let a = random_fingerprint(10, 1);
+        let b = random_fingerprint(10, 2);
+
+        assert_eq!(None, best_offset(&a, &b, None));
+        assert_eq!(None, best_offset(&a, &[], None));
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+use core::mem;
+use math;
+
+/// The number of taps in the low-pass filter applied before dropping samples.
+const FILTER_LENGTH: usize = 31;
+
+/// Halves the sample rate of a stream. The stream is low-pass filtered at a quarter of the input
+/// sample rate first so frequencies above the new nyquist frequency don't alias.
+#[derive(Clone)]
+pub struct Decimator {
+    filter: Vec<f64>,
+
+    /// Samples which still need to be filtered along with the next input.
+    history: Vec<i16>,
+}
+
+impl Decimator {
+    pub fn new() -> Decimator {
+        Decimator {
+            filter: make_half_band_filter(FILTER_LENGTH),
+            // Centers the filter on the first sample so the output isn't delayed.
+            history: vec![0; FILTER_LENGTH / 2],
+        }
+    }
+
+    pub fn process(&mut self, data: &[i16]) -> Vec<i16> {
+        let mut buffer = mem::take(&mut self.history);
+        buffer.extend_from_slice(data);
+
+        let mut output = Vec::with_capacity(buffer.len() / 2);
+        let mut start = 0;
+        while start + self.filter.len() <= buffer.len() {
+            let window = &buffer[start..(start + self.filter.len())];
+            let value: f64 = self
+                .filter
+                .iter()
+                .zip(window)
+                .map(|(coefficient, sample)| coefficient * (*sample as f64))
+                .sum();
+
+            output.push(math::round(value).max(i16::MIN as f64).min(i16::MAX as f64) as i16);
+            start += 2;
+        }
+
+        self.history = buffer.split_off(start);
+        output
+    }
+
+    /// Filters the samples held back waiting for more input.
+    pub fn flush(&mut self) -> Vec<i16> {
+        let padding = vec![0; FILTER_LENGTH / 2];
+        let output = self.process(&padding);
+        self.history.clear();
+
+        output
+    }
+}
+
+/// Builds a Blackman windowed sinc low-pass filter with its cutoff at half of the nyquist
+/// frequency. The coefficients sum to one.
+fn make_half_band_filter(tap_count: usize) -> Vec<f64> {
+    let center = (tap_count - 1) as f64 / 2.0;
+    let mut filter: Vec<f64> = (0..tap_count)
+        .map(|idx| {
+            let x = idx as f64 - center;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                math::sin(PI * x / 2.0) / (PI * x / 2.0)
+            };
+            let phase = 2.0 * PI * idx as f64 / (tap_count - 1) as f64;
+            let window = 0.42 - 0.5 * math::cos(phase) + 0.08 * math::cos(2.0 * phase);
+
+            sinc * window
+        })
+        .collect();
+
+    let sum: f64 = filter.iter().sum();
+    for coefficient in filter.iter_mut() {
+        *coefficient /= sum;
+    }
+
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{make_half_band_filter, Decimator};
+    use std::f64::consts::PI;
+
+    fn sine(frequency: f64, sample_rate: f64, length: usize) -> Vec<i16> {
+        (0..length)
+            .map(|idx| (10000.0 * (2.0 * PI * frequency * idx as f64 / sample_rate).sin()) as i16)
+            .collect()
+    }
+
+    fn peak(samples: &[i16]) -> i16 {
+        samples.iter().map(|sample| sample.abs()).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_filter_is_normalized() {
+        let filter = make_half_band_filter(31);
+
+        assert_abs_diff_eq!(1.0, filter.iter().sum::<f64>(), epsilon = 1e-12);
+        assert_abs_diff_eq!(filter[0], filter[30], epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_output_length() {
+        let mut decimator = Decimator::new();
+        let mut output = decimator.process(&[1000; 7]);
+        output.extend(decimator.process(&[1000; 13]));
+        output.extend(decimator.flush());
+
+        assert_eq!(10, output.len());
+    }
+
+    #[test]
+    fn test_keeps_low_frequencies() {
+        let mut decimator = Decimator::new();
+        let output = decimator.process(&sine(1000.0, 96000.0, 9600));
+
+        assert!(peak(&output[100..]) > 9900);
+    }
+
+    #[test]
+    fn test_removes_high_frequencies() {
+        let mut decimator = Decimator::new();
+        let output = decimator.process(&sine(40000.0, 96000.0, 9600));
+
+        assert!(peak(&output[100..]) < 100);
+    }
+}
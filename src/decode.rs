@@ -0,0 +1,302 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{self, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use algorithm::Algorithm;
+use fingerprinter::Fingerprinter;
+
+/// Decodes compressed audio files with symphonia into samples for a `Fingerprinter`.
+///
+/// MP3, FLAC, Vorbis, AAC and ALAC in MP4, and WAV files are supported.
+pub struct AudioDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn codecs::Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: u16,
+    frames: Option<u64>,
+    buffer: Option<SampleBuffer<i16>>,
+}
+
+impl AudioDecoder {
+    /// Opens a file, using its extension as a hint of its format.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<AudioDecoder> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+
+        AudioDecoder::new(Box::new(File::open(path)?), extension)
+    }
+
+    /// Probes the format of `source` and prepares to decode its first audio track.
+    pub fn new(source: Box<dyn MediaSource>, extension: Option<&str>) -> io::Result<AudioDecoder> {
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+
+        let stream = MediaSourceStream::new(source, Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(to_io_error)?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| invalid_data("no audio track found"))?;
+        let params = &track.codec_params;
+
+        let sample_rate = params
+            .sample_rate
+            .ok_or_else(|| invalid_data("the sample rate of the audio track is unknown"))?;
+        let channels = params
+            .channels
+            .map(|channels| channels.count() as u16)
+            .ok_or_else(|| invalid_data("the channel layout of the audio track is unknown"))?;
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(to_io_error)?;
+
+        Ok(AudioDecoder {
+            track_id: track.id,
+            sample_rate,
+            channels,
+            frames: params.n_frames,
+            format,
+            decoder,
+            buffer: None,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// The duration of the audio in seconds, when the container records it.
+    pub fn duration(&self) -> Option<f64> {
+        self.frames
+            .map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Decodes the next packet into interleaved samples. Packets which fail to decode are
+    /// skipped.
+    ///
+    /// # Returns
+    /// An empty vector at the end of the track.
+    pub fn read_samples(&mut self) -> io::Result<Vec<i16>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(Vec::new())
+                }
+                Err(Error::ResetRequired) => return Ok(Vec::new()),
+                Err(err) => return Err(to_io_error(err)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(Error::DecodeError(_)) => continue,
+                Err(err) => return Err(to_io_error(err)),
+            };
+
+            let spec = *decoded.spec();
+            if spec.rate != self.sample_rate || spec.channels.count() != self.channels as usize {
+                return Err(invalid_data(
+                    "the audio format changed part way through the track",
+                ));
+            }
+
+            let capacity = decoded.capacity() as u64;
+            let needed = decoded.capacity() * spec.channels.count();
+            if self
+                .buffer
+                .as_ref()
+                .is_none_or(|buffer| buffer.capacity() < needed)
+            {
+                self.buffer = Some(SampleBuffer::new(capacity, spec));
+            }
+
+            let buffer = self.buffer.as_mut().unwrap();
+            buffer.copy_interleaved_ref(decoded);
+
+            return Ok(buffer.samples().to_vec());
+        }
+    }
+
+    /// Feeds up to `max_duration` seconds of samples to `fingerprinter`, or the whole track when
+    /// it is `None`.
+    ///
+    /// # Returns
+    /// The duration of the track in seconds. Like `fpcalc`, this is the duration recorded by the
+    /// container when it is known, otherwise the duration of the audio read.
+    pub fn feed(
+        &mut self,
+        fingerprinter: &mut Fingerprinter,
+        max_duration: Option<f64>,
+    ) -> io::Result<f64> {
+        let channels = self.channels as usize;
+        let frame_limit = max_duration.map(|duration| (duration * self.sample_rate as f64) as u64);
+        let mut frames = 0;
+
+        loop {
+            let samples = self.read_samples()?;
+            if samples.is_empty() {
+                break;
+            }
+
+            let available = (samples.len() / channels) as u64;
+            let fed = match frame_limit {
+                Some(frame_limit) => available.min(frame_limit.saturating_sub(frames)),
+                None => available,
+            };
+            fingerprinter
+                .feed(&samples[..(fed as usize * channels)])
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            frames += available;
+
+            // The rest of the track is only decoded when its duration isn't recorded.
+            if fed < available && self.frames.is_some() {
+                break;
+            }
+        }
+
+        Ok(self
+            .duration()
+            .unwrap_or(frames as f64 / self.sample_rate as f64))
+    }
+}
+
+/// Fingerprints up to `max_duration` seconds of an audio file, like `fpcalc` does.
+///
+/// # Returns
+/// The finished fingerprinter and the duration of the file in seconds.
+pub fn fingerprint_file<P: AsRef<Path>>(
+    path: P,
+    algorithm: Algorithm,
+    max_duration: Option<f64>,
+) -> io::Result<(Fingerprinter, f64)> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let mut fingerprinter =
+        Fingerprinter::with_algorithm(decoder.sample_rate(), decoder.channels(), algorithm)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    let duration = decoder.feed(&mut fingerprinter, max_duration)?;
+    fingerprinter
+        .finish()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    Ok((fingerprinter, duration))
+}
+
+fn to_io_error(error: Error) -> io::Error {
+    match error {
+        Error::IoError(err) => err,
+        err => invalid_data(err),
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fingerprint_file, AudioDecoder};
+    use algorithm::Algorithm;
+    use std::env;
+    use std::fs;
+    use std::io::{Cursor, ErrorKind};
+    use std::path::PathBuf;
+    use tests;
+
+    fn write_wav(name: &str, channels: u16, samples: &[i16]) -> PathBuf {
+        let data: Vec<u8> = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes().to_vec())
+            .collect();
+
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt \x10\0\0\0\x01\0");
+        file.extend_from_slice(&channels.to_le_bytes());
+        file.extend_from_slice(&44100u32.to_le_bytes());
+        file.extend_from_slice(&(44100 * 2 * channels as u32).to_le_bytes());
+        file.extend_from_slice(&(2 * channels).to_le_bytes());
+        file.extend_from_slice(&16u16.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(&data);
+
+        let path = env::temp_dir().join(format!("chromaprint-{}-{}.wav", name, std::process::id()));
+        fs::write(&path, &file).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_fingerprint_file() {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap();
+        let path = write_wav("decode", 1, &samples);
+
+        let (fingerprinter, duration) = fingerprint_file(&path, Algorithm::Test2, None).unwrap();
+        let (limited, limited_duration) =
+            fingerprint_file(&path, Algorithm::Test2, Some(2.0)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog",
+            fingerprinter.fingerprint().compress().unwrap().encode()
+        );
+        assert_abs_diff_eq!(samples.len() as f64 / 44100.0, duration, epsilon = 1e-9);
+
+        // The duration of the whole file is reported even though only part of it is used.
+        assert_eq!(duration, limited_duration);
+        assert!(limited.fingerprint().0.len() < fingerprinter.fingerprint().0.len());
+    }
+
+    #[test]
+    fn test_stereo() {
+        let path = write_wav("stereo", 2, &[1, 2, 3, 4, 5, 6]);
+        let mut decoder = AudioDecoder::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(2, decoder.channels());
+        assert_eq!(44100, decoder.sample_rate());
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], decoder.read_samples().unwrap());
+        assert!(decoder.read_samples().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let source = Box::new(Cursor::new(vec![0u8; 1024]));
+        let error = AudioDecoder::new(source, None).err().unwrap();
+
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+}
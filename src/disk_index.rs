@@ -0,0 +1,768 @@
+//! A fingerprint index stored in a directory, so it doesn't need to be rebuilt on every start.
+//!
+//! The directory holds immutable segment files and a manifest listing the live segments. Each
+//! call to `DiskIndex::add` writes one new segment and then replaces the manifest, so a crash
+//! part way through an add leaves the previous state of the index intact. Removals are recorded
+//! in the manifest and applied to the segment files by `DiskIndex::compact`, which rewrites all
+//! of the live tracks into a single segment.
+//!
+//! All numbers are little-endian. A segment file is laid out as:
+//!
+//! ```text
+//! magic "CPIS" | version: u32 | track count: u32 | posting count: u32
+//! tracks:   (track id: u32, length: u32, data offset: u64) sorted by track id
+//! postings: (hash: u32, track id: u32, position: u32) sorted by hash
+//! data:     compressed fingerprints, as produced by `Fingerprint::compress`
+//! ```
+//!
+//! Lookups binary search the tables in place, so segments are usable straight from a memory
+//! map. The `mmap` feature maps them instead of reading them into memory.
+//!
+//! The manifest is laid out as:
+//!
+//! ```text
+//! magic "CPIM" | version: u32 | next segment id: u32 | segment count: u32 | tombstone count: u32
+//! segments:   segment id: u32, oldest first
+//! tombstones: (track id: u32, segment id: u32)
+//! ```
+//!
+//! A tombstone hides a track in every segment older than its segment id, so a track which is
+//! added again after being removed is found in the newer segment.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use algorithm::Algorithm;
+use compare::compare;
+use fingerprint_decompressor::decompress;
+use fingerprint_index::{best_offsets, hash, rank, Candidate};
+use fingerprinter::Fingerprint;
+
+const VERSION: u32 = 1;
+const SEGMENT_MAGIC: &[u8; 4] = b"CPIS";
+const MANIFEST_MAGIC: &[u8; 4] = b"CPIM";
+const MANIFEST_NAME: &str = "MANIFEST";
+
+const SEGMENT_HEADER_SIZE: usize = 16;
+const TRACK_ENTRY_SIZE: usize = 16;
+const POSTING_SIZE: usize = 12;
+
+/// A fingerprint index persisted in a directory.
+pub struct DiskIndex {
+    path: PathBuf,
+    next_segment_id: u32,
+    segments: Vec<SegmentFile>,
+    tombstones: HashMap<u32, u32>,
+
+    /// Maps each live track to the segment holding its newest version.
+    tracks: HashMap<u32, usize>,
+}
+
+impl DiskIndex {
+    /// Creates an empty index in `path`, creating the directory if needed.
+    ///
+    /// # Errors
+    /// Fails with `io::ErrorKind::AlreadyExists` if the directory already holds an index.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<DiskIndex> {
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+        if path.join(MANIFEST_NAME).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "an index already exists in this directory",
+            ));
+        }
+
+        let index = DiskIndex {
+            path: path.to_path_buf(),
+            next_segment_id: 0,
+            segments: Vec::new(),
+            tombstones: HashMap::new(),
+            tracks: HashMap::new(),
+        };
+        index.write_manifest()?;
+
+        Ok(index)
+    }
+
+    /// Opens the index previously created in `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<DiskIndex> {
+        let path = path.as_ref();
+        let manifest = fs::read(path.join(MANIFEST_NAME))?;
+
+        let mut reader = Reader::new(&manifest);
+        if reader.bytes(4)? != MANIFEST_MAGIC {
+            return Err(invalid_data("not an index manifest"));
+        }
+        check_version(reader.u32()?)?;
+
+        let next_segment_id = reader.u32()?;
+        let segment_count = reader.u32()?;
+        let tombstone_count = reader.u32()?;
+
+        let mut segments = Vec::new();
+        for _ in 0..segment_count {
+            let id = reader.u32()?;
+            segments.push(SegmentFile::open(&segment_path(path, id), id)?);
+        }
+
+        let mut tombstones = HashMap::new();
+        for _ in 0..tombstone_count {
+            let track_id = reader.u32()?;
+            tombstones.insert(track_id, reader.u32()?);
+        }
+
+        let mut index = DiskIndex {
+            path: path.to_path_buf(),
+            next_segment_id,
+            segments,
+            tombstones,
+            tracks: HashMap::new(),
+        };
+        index.load_tracks();
+
+        Ok(index)
+    }
+
+    /// Writes the given tracks to a new segment. Tracks which are already in the index are
+    /// replaced.
+    pub fn add(&mut self, tracks: &[(u32, Fingerprint)]) -> io::Result<()> {
+        let mut entries = Vec::with_capacity(tracks.len());
+        for &(track_id, ref fingerprint) in tracks {
+            let compressed = fingerprint
+                .compress()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            entries.push((track_id, fingerprint.0.to_vec(), compressed.0));
+        }
+
+        self.write_segment(entries)?;
+        if let Err(err) = self.write_manifest() {
+            // The manifest on disk doesn't list the new segment, so it would be gone on reopen.
+            self.discard_last_segment();
+            return Err(err);
+        }
+        self.load_tracks();
+
+        Ok(())
+    }
+
+    /// Removes a track from the index. The space it takes up is reclaimed by `compact`.
+    ///
+    /// # Returns
+    /// Whether the track was in the index.
+    pub fn remove(&mut self, track_id: u32) -> io::Result<bool> {
+        let segment = match self.tracks.remove(&track_id) {
+            Some(segment) => segment,
+            None => return Ok(false),
+        };
+
+        let previous = self.tombstones.insert(track_id, self.next_segment_id);
+        if let Err(err) = self.write_manifest() {
+            self.tracks.insert(track_id, segment);
+            match previous {
+                Some(previous) => self.tombstones.insert(track_id, previous),
+                None => self.tombstones.remove(&track_id),
+            };
+            return Err(err);
+        }
+
+        Ok(true)
+    }
+
+    /// Rewrites the live tracks into a single segment, dropping removed and replaced tracks and
+    /// deleting the old segment files.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let mut track_ids: Vec<u32> = self.tracks.keys().cloned().collect();
+        track_ids.sort_unstable();
+
+        let mut entries = Vec::with_capacity(track_ids.len());
+        for track_id in track_ids {
+            let compressed = self.compressed(track_id)?.unwrap().to_vec();
+            let (fingerprint, _) = decompress(&compressed).map_err(invalid_data)?;
+            entries.push((track_id, fingerprint, compressed));
+        }
+
+        let old_segments: Vec<u32> = self.segments.iter().map(|segment| segment.id).collect();
+        let segments = mem::take(&mut self.segments);
+        let tombstones = mem::take(&mut self.tombstones);
+
+        let result = self.write_segment(entries).and_then(|_| {
+            self.write_manifest()
+                .inspect_err(|_| self.discard_last_segment())
+        });
+        if let Err(err) = result {
+            // Keep using the old segments, which are still listed in the manifest on disk.
+            self.segments = segments;
+            self.tombstones = tombstones;
+            return Err(err);
+        }
+
+        // The segments may still be mapped, so they are closed before being deleted.
+        drop(segments);
+        for id in old_segments {
+            fs::remove_file(segment_path(&self.path, id))?;
+        }
+
+        self.load_tracks();
+
+        Ok(())
+    }
+
+    pub fn contains(&self, track_id: u32) -> bool {
+        self.tracks.contains_key(&track_id)
+    }
+
+    /// The number of live tracks.
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// The number of segment files, which grows with each `add` until the index is compacted.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The compressed fingerprint stored for a track.
+    pub fn compressed(&self, track_id: u32) -> io::Result<Option<&[u8]>> {
+        match self.tracks.get(&track_id) {
+            Some(&segment) => self.segments[segment].fingerprint(track_id),
+            None => Ok(None),
+        }
+    }
+
+    /// The raw fingerprint stored for a track and the algorithm it was computed with.
+    pub fn get(&self, track_id: u32) -> io::Result<Option<(Vec<u32>, Algorithm)>> {
+        match self.compressed(track_id)? {
+            Some(compressed) => decompress(compressed).map(Some).map_err(invalid_data),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the tracks which share the most item hashes with `query` at a consistent offset, like
+    /// `FingerprintIndex::search`.
+    pub fn search(&self, query: &[u32], max_candidates: usize) -> io::Result<Vec<Candidate>> {
+        let mut counts: HashMap<(u32, isize), usize> = HashMap::new();
+        for (segment_idx, segment) in self.segments.iter().enumerate() {
+            for (query_position, item) in query.iter().enumerate() {
+                for (track_id, position) in segment.postings(hash(*item)) {
+                    if self.tracks.get(&track_id) != Some(&segment_idx) {
+                        continue;
+                    }
+
+                    let offset = position as isize - query_position as isize;
+                    *counts.entry((track_id, offset)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for (track_id, (offset, hits)) in best_offsets(counts) {
+            let (fingerprint, _) = self.get(track_id)?.unwrap();
+            if let Some(similarity) = compare(&fingerprint, query, offset) {
+                candidates.push(Candidate {
+                    track_id,
+                    offset,
+                    hits,
+                    similarity,
+                });
+            }
+        }
+
+        rank(&mut candidates, max_candidates);
+
+        Ok(candidates)
+    }
+
+    /// Finds the newest segment holding each track which hasn't been removed.
+    fn load_tracks(&mut self) {
+        self.tracks.clear();
+        for (segment_idx, segment) in self.segments.iter().enumerate() {
+            for track_id in segment.track_ids() {
+                let removed = self
+                    .tombstones
+                    .get(&track_id)
+                    .is_some_and(|&before| segment.id < before);
+
+                if !removed {
+                    self.tracks.insert(track_id, segment_idx);
+                }
+            }
+        }
+    }
+
+    /// Writes `(track id, fingerprint, compressed fingerprint)` entries to a new segment file and
+    /// adds it to the in-memory list of segments.
+    fn write_segment(&mut self, mut entries: Vec<(u32, Vec<u32>, Vec<u8>)>) -> io::Result<()> {
+        // When a track is given more than once, the last one wins.
+        entries.reverse();
+        entries.sort_by_key(|entry| entry.0);
+        entries.dedup_by_key(|entry| entry.0);
+
+        let mut postings = Vec::new();
+        for &(track_id, ref fingerprint, _) in &entries {
+            for (position, item) in fingerprint.iter().enumerate() {
+                postings.push((hash(*item), track_id, position as u32));
+            }
+        }
+        postings.sort_unstable();
+
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(SEGMENT_MAGIC);
+        put_u32(&mut buffer, VERSION);
+        put_u32(&mut buffer, entries.len() as u32);
+        put_u32(&mut buffer, postings.len() as u32);
+
+        let mut data_offset = (SEGMENT_HEADER_SIZE
+            + entries.len() * TRACK_ENTRY_SIZE
+            + postings.len() * POSTING_SIZE) as u64;
+        for &(track_id, _, ref compressed) in &entries {
+            put_u32(&mut buffer, track_id);
+            put_u32(&mut buffer, compressed.len() as u32);
+            buffer.extend_from_slice(&data_offset.to_le_bytes());
+            data_offset += compressed.len() as u64;
+        }
+
+        for (hash, track_id, position) in postings {
+            put_u32(&mut buffer, hash);
+            put_u32(&mut buffer, track_id);
+            put_u32(&mut buffer, position);
+        }
+
+        for (_, _, compressed) in entries {
+            buffer.extend_from_slice(&compressed);
+        }
+
+        let id = self.next_segment_id;
+        let path = segment_path(&self.path, id);
+        write_atomically(&path, &buffer)?;
+
+        self.next_segment_id += 1;
+        self.segments.push(SegmentFile::open(&path, id)?);
+
+        Ok(())
+    }
+
+    /// Removes the segment added by the last `write_segment`, after the manifest failed to list
+    /// it.
+    fn discard_last_segment(&mut self) {
+        if let Some(segment) = self.segments.pop() {
+            // The segment may still be mapped, so it is closed before being deleted. The index is
+            // already failing, so a file which can't be deleted is only left behind unlisted.
+            let id = segment.id;
+            drop(segment);
+            let _ = fs::remove_file(segment_path(&self.path, id));
+            self.next_segment_id = id;
+        }
+    }
+
+    fn write_manifest(&self) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(MANIFEST_MAGIC);
+        put_u32(&mut buffer, VERSION);
+        put_u32(&mut buffer, self.next_segment_id);
+        put_u32(&mut buffer, self.segments.len() as u32);
+        put_u32(&mut buffer, self.tombstones.len() as u32);
+
+        for segment in &self.segments {
+            put_u32(&mut buffer, segment.id);
+        }
+
+        for (&track_id, &segment_id) in &self.tombstones {
+            put_u32(&mut buffer, track_id);
+            put_u32(&mut buffer, segment_id);
+        }
+
+        write_atomically(&self.path.join(MANIFEST_NAME), &buffer)
+    }
+}
+
+/// The contents of a segment file.
+enum SegmentData {
+    Owned(Vec<u8>),
+
+    #[cfg(feature = "mmap")]
+    Mapped(Mmap),
+}
+
+impl Deref for SegmentData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match *self {
+            SegmentData::Owned(ref data) => data,
+
+            #[cfg(feature = "mmap")]
+            SegmentData::Mapped(ref data) => data,
+        }
+    }
+}
+
+struct SegmentFile {
+    id: u32,
+    data: SegmentData,
+    track_count: usize,
+    posting_count: usize,
+}
+
+impl SegmentFile {
+    fn open(path: &Path, id: u32) -> io::Result<SegmentFile> {
+        let data = SegmentFile::load(path)?;
+
+        let (track_count, posting_count) = {
+            let mut reader = Reader::new(&data);
+            if reader.bytes(4)? != SEGMENT_MAGIC {
+                return Err(invalid_data("not an index segment"));
+            }
+            check_version(reader.u32()?)?;
+
+            (reader.u32()? as usize, reader.u32()? as usize)
+        };
+
+        let tables_size = SEGMENT_HEADER_SIZE as u64
+            + track_count as u64 * TRACK_ENTRY_SIZE as u64
+            + posting_count as u64 * POSTING_SIZE as u64;
+        if tables_size > data.len() as u64 {
+            return Err(invalid_data("truncated index segment"));
+        }
+
+        Ok(SegmentFile {
+            id,
+            data,
+            track_count,
+            posting_count,
+        })
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn load(path: &Path) -> io::Result<SegmentData> {
+        Ok(SegmentData::Owned(fs::read(path)?))
+    }
+
+    #[cfg(feature = "mmap")]
+    fn load(path: &Path) -> io::Result<SegmentData> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Ok(SegmentData::Owned(Vec::new()));
+        }
+
+        // Segment files are never modified once they are written, and are only deleted after
+        // they are dropped.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        Ok(SegmentData::Mapped(mmap))
+    }
+
+    fn track_entry(&self, idx: usize) -> (u32, usize, u64) {
+        let offset = SEGMENT_HEADER_SIZE + idx * TRACK_ENTRY_SIZE;
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.data[(offset + 8)..(offset + 16)]);
+
+        (
+            read_u32(&self.data, offset),
+            read_u32(&self.data, offset + 4) as usize,
+            u64::from_le_bytes(bytes),
+        )
+    }
+
+    fn track_ids(&self) -> Vec<u32> {
+        (0..self.track_count)
+            .map(|idx| self.track_entry(idx).0)
+            .collect()
+    }
+
+    fn fingerprint(&self, track_id: u32) -> io::Result<Option<&[u8]>> {
+        let idx = match lower_bound(self.track_count, |idx| self.track_entry(idx).0 < track_id) {
+            idx if idx < self.track_count && self.track_entry(idx).0 == track_id => idx,
+            _ => return Ok(None),
+        };
+
+        let (_, length, offset) = self.track_entry(idx);
+        let end = offset + length as u64;
+        if end > self.data.len() as u64 {
+            return Err(invalid_data("truncated index segment"));
+        }
+
+        Ok(Some(&self.data[(offset as usize)..(end as usize)]))
+    }
+
+    fn posting(&self, idx: usize) -> (u32, u32, u32) {
+        let offset = SEGMENT_HEADER_SIZE + self.track_count * TRACK_ENTRY_SIZE + idx * POSTING_SIZE;
+
+        (
+            read_u32(&self.data, offset),
+            read_u32(&self.data, offset + 4),
+            read_u32(&self.data, offset + 8),
+        )
+    }
+
+    /// The track ids and positions of the items with the given hash.
+    fn postings<'a>(&'a self, hash: u32) -> impl Iterator<Item = (u32, u32)> + 'a {
+        let start = lower_bound(self.posting_count, |idx| self.posting(idx).0 < hash);
+
+        (start..self.posting_count)
+            .map(move |idx| self.posting(idx))
+            .take_while(move |posting| posting.0 == hash)
+            .map(|(_, track_id, position)| (track_id, position))
+    }
+}
+
+/// Reads fields from the start of a buffer in order.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, position: 0 }
+    }
+
+    fn bytes(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..(self.position + length))
+            .ok_or_else(|| invalid_data("unexpected end of index file"))?;
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+
+        Ok(read_u32(bytes, 0))
+    }
+}
+
+/// Finds the first index in `0..len` for which `is_before` is false.
+fn lower_bound<F: Fn(usize) -> bool>(len: usize, is_before: F) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if is_before(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..(offset + 4)]);
+
+    u32::from_le_bytes(bytes)
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn check_version(version: u32) -> io::Result<()> {
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported index version {}",
+            version
+        )));
+    }
+
+    Ok(())
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn segment_path(path: &Path, id: u32) -> PathBuf {
+    path.join(format!("segment-{:08}.seg", id))
+}
+
+/// Writes `data` to a temporary file next to `path` and moves it into place once it is synced,
+/// so readers see either the old or the new contents.
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temporary_path = path.with_extension("tmp");
+
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+
+    fs::rename(&temporary_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskIndex;
+    use algorithm::Algorithm;
+    use fingerprinter::Fingerprint;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    fn random_fingerprint(length: usize, seed: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect()
+    }
+
+    fn scratch_directory(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("chromaprint-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+
+        path
+    }
+
+    fn add_tracks(index: &mut DiskIndex, track_ids: &[u32]) {
+        let fingerprints: Vec<Vec<u32>> = track_ids
+            .iter()
+            .map(|&track_id| random_fingerprint(300, track_id))
+            .collect();
+        let tracks: Vec<(u32, Fingerprint)> = track_ids
+            .iter()
+            .zip(&fingerprints)
+            .map(|(&track_id, fingerprint)| (track_id, Fingerprint(fingerprint, Algorithm::Test2)))
+            .collect();
+
+        index.add(&tracks).unwrap();
+    }
+
+    #[test]
+    fn test_reopen() {
+        let path = scratch_directory("reopen");
+        {
+            let mut index = DiskIndex::create(&path).unwrap();
+            add_tracks(&mut index, &[1, 2, 3]);
+            add_tracks(&mut index, &[4, 5]);
+        }
+
+        let index = DiskIndex::open(&path).unwrap();
+        assert_eq!(5, index.len());
+        assert_eq!(2, index.segment_count());
+        assert_eq!(
+            Some((random_fingerprint(300, 4), Algorithm::Test2)),
+            index.get(4).unwrap()
+        );
+
+        let query = &random_fingerprint(300, 2)[100..200];
+        let candidates = index.search(query, 3).unwrap();
+        assert_eq!(1, candidates.len());
+        assert_eq!(2, candidates[0].track_id);
+        assert_eq!(100, candidates[0].offset);
+        assert_eq!(0, candidates[0].similarity.bit_errors);
+
+        assert_eq!(
+            io::ErrorKind::AlreadyExists,
+            DiskIndex::create(&path).err().unwrap().kind()
+        );
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_remove_and_compact() {
+        let path = scratch_directory("compact");
+        let mut index = DiskIndex::create(&path).unwrap();
+        add_tracks(&mut index, &[1, 2, 3]);
+        add_tracks(&mut index, &[3, 4]);
+
+        assert!(index.remove(1).unwrap());
+        assert!(!index.remove(1).unwrap());
+        assert!(index.remove(4).unwrap());
+        add_tracks(&mut index, &[4]);
+
+        let reopened = DiskIndex::open(&path).unwrap();
+        assert_eq!(3, reopened.len());
+        assert!(!reopened.contains(1));
+        assert!(reopened.contains(4));
+        assert!(reopened
+            .search(&random_fingerprint(300, 1), 5)
+            .unwrap()
+            .is_empty());
+
+        index.compact().unwrap();
+        assert_eq!(1, index.segment_count());
+        assert_eq!(1, fs::read_dir(&path).unwrap().count() - 1);
+
+        let reopened = DiskIndex::open(&path).unwrap();
+        let mut track_ids: Vec<u32> = (0..6).filter(|&id| reopened.contains(id)).collect();
+        track_ids.sort_unstable();
+        assert_eq!(vec![2, 3, 4], track_ids);
+        assert_eq!(
+            3,
+            reopened.search(&random_fingerprint(300, 3), 5).unwrap()[0].track_id
+        );
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_manifest_write() {
+        let path = scratch_directory("manifest");
+        let mut index = DiskIndex::create(&path).unwrap();
+        add_tracks(&mut index, &[1, 2]);
+
+        // The manifest can't be replaced while a directory is in the way of its temporary file.
+        fs::create_dir(path.join("MANIFEST.tmp")).unwrap();
+
+        let fingerprint = random_fingerprint(300, 3);
+        assert!(index
+            .add(&[(3, Fingerprint(&fingerprint, Algorithm::Test2))])
+            .is_err());
+        assert!(!index.contains(3));
+        assert_eq!(2, index.len());
+        assert_eq!(1, index.segment_count());
+        assert!(index.search(&fingerprint, 5).unwrap().is_empty());
+
+        assert!(index.compact().is_err());
+        assert_eq!(1, index.segment_count());
+
+        assert!(index.remove(1).is_err());
+        assert!(index.contains(1));
+
+        // Only the manifest, its temporary file and the first segment are left.
+        assert_eq!(3, fs::read_dir(&path).unwrap().count());
+
+        fs::remove_dir(path.join("MANIFEST.tmp")).unwrap();
+        add_tracks(&mut index, &[3]);
+        assert!(index.remove(2).unwrap());
+
+        let reopened = DiskIndex::open(&path).unwrap();
+        let mut track_ids: Vec<u32> = (0..5).filter(|&id| reopened.contains(id)).collect();
+        track_ids.sort_unstable();
+        assert_eq!(vec![1, 3], track_ids);
+        assert_eq!(2, reopened.segment_count());
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_manifest() {
+        let path = scratch_directory("corrupt");
+        DiskIndex::create(&path).unwrap();
+        fs::write(path.join("MANIFEST"), b"CPIM\x02\x00\x00\x00").unwrap();
+
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            DiskIndex::open(&path).err().unwrap().kind()
+        );
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use alloc::vec::Vec;
+
+/// Averages interleaved multi-channel audio into a single channel.
+#[derive(Clone)]
+pub struct Downmixer {
+    channels: usize,
+
+    /// The samples of a frame which was split across calls to `process`.
+    partial_frame: Vec<i16>,
+}
+
+impl Downmixer {
+    pub fn new(channels: u16) -> Downmixer {
+        Downmixer {
+            channels: channels as usize,
+            partial_frame: Vec::with_capacity(channels as usize),
+        }
+    }
+
+    /// Downmixes interleaved samples. Samples of an incomplete trailing frame are kept until the
+    /// rest of the frame is passed in the next call.
+    pub fn process(&mut self, mut data: &[i16]) -> Vec<i16> {
+        let mut output =
+            Vec::with_capacity((self.partial_frame.len() + data.len()) / self.channels);
+
+        if !self.partial_frame.is_empty() {
+            let missing = usize::min(self.channels - self.partial_frame.len(), data.len());
+            self.partial_frame.extend_from_slice(&data[..missing]);
+            data = &data[missing..];
+
+            if self.partial_frame.len() < self.channels {
+                return output;
+            }
+
+            output.push(mix(&self.partial_frame));
+            self.partial_frame.clear();
+        }
+
+        let mut frames = data.chunks_exact(self.channels);
+        output.extend(frames.by_ref().map(mix));
+        self.partial_frame.extend_from_slice(frames.remainder());
+
+        output
+    }
+}
+
+fn mix(frame: &[i16]) -> i16 {
+    let sum: i32 = frame.iter().map(|sample| *sample as i32).sum();
+    (sum / frame.len() as i32) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Downmixer;
+
+    #[test]
+    fn test_stereo() {
+        let mut downmixer = Downmixer::new(2);
+
+        assert_eq!(vec![1, 3, -1], downmixer.process(&[0, 2, 2, 4, -1, -1]));
+    }
+
+    #[test]
+    fn test_no_overflow() {
+        let mut downmixer = Downmixer::new(2);
+
+        assert_eq!(
+            vec![i16::MAX, i16::MIN],
+            downmixer.process(&[i16::MAX, i16::MAX, i16::MIN, i16::MIN])
+        );
+    }
+
+    #[test]
+    fn test_split_frames() {
+        let mut downmixer = Downmixer::new(6);
+
+        assert!(downmixer.process(&[6, 6, 6]).is_empty());
+        assert!(downmixer.process(&[6]).is_empty());
+        assert_eq!(vec![6, 1], downmixer.process(&[6, 6, 1, 1, 1, 1, 1, 1, 2]));
+        assert_eq!(vec![2], downmixer.process(&[2, 2, 2, 2, 2]));
+    }
+}
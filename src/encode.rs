@@ -1,4 +1,5 @@
-use base64::{CharacterSet, Config};
+use base64::{CharacterSet, Config, DecodeError};
+use error::ChromaprintError;
 
 fn config() -> Config {
     Config::new(CharacterSet::UrlSafe, false)
@@ -7,3 +8,63 @@ fn config() -> Config {
 pub fn encode(fingerprint: &[u8]) -> String {
     base64::encode_config(fingerprint, config())
 }
+
+/// Decodes the output of `encode`. Padding, surrounding whitespace and the standard base64
+/// alphabet are also accepted.
+pub fn decode(encoded: &str) -> Result<Vec<u8>, ChromaprintError> {
+    let normalized: String = encoded
+        .trim()
+        .trim_end_matches('=')
+        .chars()
+        .map(|character| match character {
+            '+' => '-',
+            '/' => '_',
+            _ => character,
+        })
+        .collect();
+
+    base64::decode_config(&normalized, config()).map_err(|err| match err {
+        DecodeError::InvalidByte(offset, byte) => {
+            ChromaprintError::InvalidCharacter(offset, byte as char)
+        }
+        DecodeError::InvalidLength | DecodeError::InvalidLastSymbol(_, _) => {
+            ChromaprintError::InvalidEncoding
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use error::ChromaprintError;
+
+    #[test]
+    fn test_round_trip() {
+        let data = [1, 0, 0, 2, 0xfb, 0xff, 0x3e];
+        let encoded = encode(&data);
+
+        assert_eq!("AQAAAvv_Pg", encoded);
+        assert_eq!(Ok(data.to_vec()), decode(&encoded));
+    }
+
+    #[test]
+    fn test_padded_standard_alphabet() {
+        assert_eq!(
+            Ok(vec![1, 0, 0, 2, 0xfb, 0xff, 0x3e]),
+            decode("AQAAAvv/Pg==\n")
+        );
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(
+            Err(ChromaprintError::InvalidCharacter(4, '!')),
+            decode("AQAA!vv_Pg")
+        );
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        assert_eq!(Err(ChromaprintError::InvalidEncoding), decode("AQAAA"));
+    }
+}
@@ -0,0 +1,70 @@
+use core::error::Error;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChromaprintError {
+    /// The sample rate is zero or too high to be resampled.
+    InvalidSampleRate(u32),
+
+    /// The audio has no channels.
+    InvalidChannelCount(u16),
+
+    /// Audio was fed to or flushed from a fingerprinter which was already finished.
+    AlreadyFinished,
+
+    /// A fingerprinter was configured after audio was fed to it.
+    AlreadyStarted,
+
+    /// The fingerprint has more items than can be stored in the header of a compressed
+    /// fingerprint.
+    FingerprintTooLong(usize),
+
+    /// A compressed fingerprint ended before all of its items were read.
+    TruncatedFingerprint,
+
+    /// A compressed fingerprint contains data which doesn't decompress into valid items.
+    CorruptFingerprint,
+
+    /// A compressed fingerprint was computed with an algorithm which isn't known.
+    UnknownAlgorithm(u8),
+
+    /// An encoded fingerprint contains a character which isn't part of the base64 alphabet at
+    /// the given offset.
+    InvalidCharacter(usize, char),
+
+    /// An encoded fingerprint isn't valid base64.
+    InvalidEncoding,
+}
+
+impl fmt::Display for ChromaprintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChromaprintError::InvalidSampleRate(sample_rate) => {
+                write!(f, "invalid sample rate: {}", sample_rate)
+            }
+            ChromaprintError::InvalidChannelCount(channels) => {
+                write!(f, "invalid number of channels: {}", channels)
+            }
+            ChromaprintError::AlreadyFinished => write!(f, "the fingerprinter is already finished"),
+            ChromaprintError::AlreadyStarted => {
+                write!(f, "audio was already fed to the fingerprinter")
+            }
+            ChromaprintError::FingerprintTooLong(length) => write!(
+                f,
+                "fingerprint of {} items is too long to be compressed",
+                length
+            ),
+            ChromaprintError::TruncatedFingerprint => write!(f, "the fingerprint is truncated"),
+            ChromaprintError::CorruptFingerprint => write!(f, "the fingerprint is corrupt"),
+            ChromaprintError::UnknownAlgorithm(id) => write!(f, "unknown algorithm: {}", id),
+            ChromaprintError::InvalidCharacter(offset, character) => write!(
+                f,
+                "invalid character {:?} at offset {} of encoded fingerprint",
+                character, offset
+            ),
+            ChromaprintError::InvalidEncoding => write!(f, "the fingerprint isn't valid base64"),
+        }
+    }
+}
+
+impl Error for ChromaprintError {}
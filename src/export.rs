@@ -0,0 +1,231 @@
+//! Renders chroma features and fingerprints as grayscale images, to see where two fingerprints
+//! differ. Time runs down the images, one row per frame or sub-fingerprint.
+
+#[cfg(feature = "png")]
+use png;
+use std::cmp;
+use std::io::{self, Write};
+
+use fingerprinter::{ChromaFeatures, Fingerprint};
+
+/// The shade of the columns separating the panels of a diff.
+const SEPARATOR: u8 = 128;
+
+/// An 8-bit grayscale image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// Renders the normalized chroma features, the rows the sub-fingerprints are computed from,
+    /// with a column for each note starting at A.
+    pub fn chroma(rows: &[ChromaFeatures]) -> GrayImage {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| row.normalized.iter().map(|&value| to_shade(value)))
+            .collect();
+
+        GrayImage {
+            width: 12,
+            height: rows.len(),
+            pixels,
+        }
+    }
+
+    /// Renders the bits of each sub-fingerprint from the least significant one, with set bits in
+    /// white.
+    pub fn fingerprint(fingerprint: &Fingerprint) -> GrayImage {
+        let mut image = GrayImage::new(32, fingerprint.0.len());
+        for (row, &item) in fingerprint.0.iter().enumerate() {
+            image.draw_bits(0, row, item);
+        }
+
+        image
+    }
+
+    /// Renders `a`, `b` and the bits which differ between them side by side, where they overlap
+    /// when `b` starts `offset` items into `a` like in `Fingerprint::compare`.
+    pub fn fingerprint_diff(a: &Fingerprint, b: &Fingerprint, offset: isize) -> GrayImage {
+        let (a, b) = if offset >= 0 {
+            (a.0.get(offset as usize..).unwrap_or(&[]), b.0)
+        } else {
+            (a.0, b.0.get(offset.unsigned_abs()..).unwrap_or(&[]))
+        };
+
+        let mut image = GrayImage::new(32 * 3 + 2, cmp::min(a.len(), b.len()));
+        for (row, (&a, &b)) in a.iter().zip(b).enumerate() {
+            image.draw_bits(0, row, a);
+            image.pixels[row * image.width + 32] = SEPARATOR;
+            image.draw_bits(33, row, b);
+            image.pixels[row * image.width + 65] = SEPARATOR;
+            image.draw_bits(66, row, a ^ b);
+        }
+
+        image
+    }
+
+    fn new(width: usize, height: usize) -> GrayImage {
+        GrayImage {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    fn draw_bits(&mut self, x: usize, y: usize, bits: u32) {
+        let start = y * self.width + x;
+        for (bit, pixel) in self.pixels[start..(start + 32)].iter_mut().enumerate() {
+            if bits & (1 << bit) != 0 {
+                *pixel = 255;
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The shades of the pixels row by row, from black at 0 to white at 255.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Writes the image as a binary PGM.
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)
+    }
+
+    /// Writes the image as a PNG. PNGs can't be empty, so this fails when there are no rows.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(to_io_error)?;
+        writer.write_image_data(&self.pixels).map_err(to_io_error)?;
+        writer.finish().map_err(to_io_error)
+    }
+}
+
+/// Maps a value between 0 and 1 to a shade.
+fn to_shade(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(feature = "png")]
+fn to_io_error(error: png::EncodingError) -> io::Error {
+    match error {
+        png::EncodingError::IoError(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidInput, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GrayImage;
+    use algorithm::Algorithm;
+    use fingerprinter::{ChromaFeatures, Fingerprint};
+
+    #[test]
+    fn test_chroma() {
+        let mut normalized = [0.0; 12];
+        normalized[0] = 1.0;
+        normalized[3] = 0.5;
+        let row = ChromaFeatures {
+            timestamp: 0.0,
+            raw: [0.0; 12],
+            filtered: [0.0; 12],
+            normalized,
+        };
+
+        let image = GrayImage::chroma(&[row, row]);
+        assert_eq!((12, 2), (image.width(), image.height()));
+        assert_eq!(&[255, 0, 0, 128, 0], &image.pixels()[12..17]);
+    }
+
+    #[test]
+    fn test_write_pgm() {
+        let image = GrayImage::fingerprint(&Fingerprint(&[0b101, 1 << 31], Algorithm::default()));
+
+        let mut pgm = Vec::new();
+        image.write_pgm(&mut pgm).unwrap();
+
+        let header = b"P5\n32 2\n255\n";
+        assert_eq!(&header[..], &pgm[..header.len()]);
+
+        let pixels = &pgm[header.len()..];
+        assert_eq!(64, pixels.len());
+        assert_eq!(&[255, 0, 255, 0], &pixels[0..4]);
+        assert_eq!(255, pixels[63]);
+        assert_eq!(
+            1,
+            pixels
+                .iter()
+                .skip(32)
+                .filter(|&&pixel| pixel == 255)
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_diff() {
+        let a = [1, 2, 3, 4];
+        let b = [3, 5];
+
+        let image = GrayImage::fingerprint_diff(
+            &Fingerprint(&a, Algorithm::default()),
+            &Fingerprint(&b, Algorithm::default()),
+            2,
+        );
+        assert_eq!((98, 2), (image.width(), image.height()));
+
+        // The second rows are 4, 5 and 4 ^ 5 = 1.
+        let row = &image.pixels()[98..];
+        assert_eq!(&[0, 0, 255], &row[0..3]);
+        assert_eq!(128, row[32]);
+        assert_eq!(&[255, 0, 255], &row[33..36]);
+        assert_eq!(128, row[65]);
+        assert_eq!(&[255, 0, 0], &row[66..69]);
+
+        let empty = GrayImage::fingerprint_diff(
+            &Fingerprint(&a, Algorithm::default()),
+            &Fingerprint(&b, Algorithm::default()),
+            -2,
+        );
+        assert_eq!(0, empty.height());
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_write_png() {
+        use png;
+        use std::io::Cursor;
+
+        let image = GrayImage::fingerprint(&Fingerprint(&[0b101, 1 << 31], Algorithm::default()));
+
+        let mut encoded = Vec::new();
+        image.write_png(&mut encoded).unwrap();
+
+        let mut reader = png::Decoder::new(Cursor::new(encoded)).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((32, 2), (info.width, info.height));
+        assert_eq!(png::ColorType::Grayscale, info.color_type);
+        assert_eq!(image.pixels(), &pixels[..]);
+
+        assert!(
+            GrayImage::fingerprint(&Fingerprint(&[], Algorithm::default()))
+                .write_png(Vec::new())
+                .is_err()
+        );
+    }
+}
@@ -1,52 +1,77 @@
+#[cfg(feature = "rustfft")]
 use rustfft::algorithm::Radix4;
+#[cfg(feature = "rustfft")]
 use rustfft::num_complex::Complex;
+#[cfg(feature = "rustfft")]
 use rustfft::num_traits::Zero;
+#[cfg(feature = "rustfft")]
 use rustfft::FFT;
 
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+use math;
+use shared::Shared;
 use slicer::FixedSlicer;
-use std::f32::consts::PI;
-
-const FRAME_SIZE: usize = 4096;
-const OVERLAP: usize = FRAME_SIZE - FRAME_SIZE / 3;
 
+/// Clones share the FFT plan and the window, which only depend on the frame size.
+#[derive(Clone)]
 pub struct Fft {
-    slicer: Option<FixedSlicer<i16>>,
-    fft: Radix4<f32>,
-    hamming_window: Vec<f32>,
+    slicer: FixedSlicer<i16>,
+    plan: Shared<Plan>,
+    hamming_window: Shared<[f32]>,
 }
 
 impl Fft {
-    pub fn new() -> Fft {
+    pub fn new(frame_size: usize, overlap: usize) -> Fft {
         Fft {
-            slicer: Some(FixedSlicer::new(FRAME_SIZE, FRAME_SIZE - OVERLAP)),
-            fft: Radix4::new(FRAME_SIZE, false),
-            hamming_window: prepare_hamming_window(FRAME_SIZE, 1.0 / ::std::i16::MAX as f32),
+            slicer: FixedSlicer::new(frame_size, frame_size - overlap),
+            plan: Shared::new(Plan::new(frame_size)),
+            hamming_window: prepare_hamming_window(frame_size, 1.0 / i16::MAX as f32).into(),
         }
     }
 
     pub fn consume<C: FnMut(Vec<f64>)>(&mut self, data: &[i16], mut consumer: C) {
-        let mut slicer = self.slicer.take().unwrap();
+        let plan = &self.plan;
+        let hamming_window = &self.hamming_window;
 
-        slicer.process(data, |vec| {
-            let mut converted: Vec<Complex<f32>> = vec
+        self.slicer.process(data, |vec| {
+            let windowed: Vec<f32> = vec
                 .into_iter()
                 .enumerate()
-                .map(|(idx, data)| self.hamming_window[idx] * (data as f32))
-                .map(|num| Complex::new(num, 0.0))
+                .map(|(idx, data)| hamming_window[idx] * (data as f32))
                 .collect();
 
-            let mut output: Vec<Complex<f32>> = vec![Complex::zero(); FRAME_SIZE];
-            self.fft.process(&mut converted, &mut output);
-
-            let folded = fold_output(&output);
-            consumer(folded);
+            consumer(plan.power_spectrum(&windowed));
         });
+    }
+}
+
+/// Computes the power spectrum of a frame with rustfft.
+#[cfg(feature = "rustfft")]
+struct Plan {
+    fft: Radix4<f32>,
+}
+
+#[cfg(feature = "rustfft")]
+impl Plan {
+    fn new(frame_size: usize) -> Plan {
+        Plan {
+            fft: Radix4::new(frame_size, false),
+        }
+    }
+
+    fn power_spectrum(&self, frame: &[f32]) -> Vec<f64> {
+        let mut input: Vec<Complex<f32>> =
+            frame.iter().map(|&num| Complex::new(num, 0.0)).collect();
+        let mut output: Vec<Complex<f32>> = vec![Complex::zero(); frame.len()];
+        self.fft.process(&mut input, &mut output);
 
-        self.slicer = Some(slicer);
+        fold_output(&output)
     }
 }
 
-pub fn fold_output(fft: &[Complex<f32>]) -> Vec<f64> {
+#[cfg(feature = "rustfft")]
+fn fold_output(fft: &[Complex<f32>]) -> Vec<f64> {
     let half_input = fft.len() / 2;
     let mut output = vec![0.0; half_input + 1];
 
@@ -58,11 +83,80 @@ pub fn fold_output(fft: &[Complex<f32>]) -> Vec<f64> {
     output
 }
 
+/// Computes the power spectrum of a frame with an iterative radix-2 FFT which only needs `core`
+/// and `alloc`. Used when the `rustfft` feature is disabled.
+#[cfg(not(feature = "rustfft"))]
+struct Plan {
+    /// `e^(-2 pi i k / frame_size)` for the first half of the frame.
+    twiddles: Vec<(f32, f32)>,
+}
+
+#[cfg(not(feature = "rustfft"))]
+impl Plan {
+    fn new(frame_size: usize) -> Plan {
+        assert!(
+            frame_size.is_power_of_two() && frame_size > 1,
+            "the frame size must be a power of two"
+        );
+
+        let twiddles = (0..frame_size / 2)
+            .map(|k| {
+                let angle = -2.0 * core::f64::consts::PI * k as f64 / frame_size as f64;
+                (math::cos(angle) as f32, math::sin(angle) as f32)
+            })
+            .collect();
+
+        Plan { twiddles }
+    }
+
+    fn power_spectrum(&self, frame: &[f32]) -> Vec<f64> {
+        let size = frame.len();
+        let bits = size.trailing_zeros();
+
+        // The imaginary parts start at zero, so only the real parts need reordering.
+        let mut re = frame.to_vec();
+        let mut im = vec![0.0f32; size];
+        for idx in 0..size {
+            let reversed = idx.reverse_bits() >> (usize::BITS - bits);
+            if reversed > idx {
+                re.swap(idx, reversed);
+            }
+        }
+
+        let mut length = 2;
+        while length <= size {
+            let half = length / 2;
+            let stride = size / length;
+
+            for start in (0..size).step_by(length) {
+                for k in 0..half {
+                    let (w_re, w_im) = self.twiddles[k * stride];
+                    let (a, b) = (start + k, start + k + half);
+
+                    let t_re = re[b] * w_re - im[b] * w_im;
+                    let t_im = re[b] * w_im + im[b] * w_re;
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                }
+            }
+
+            length *= 2;
+        }
+
+        (0..(size / 2 + 1))
+            .map(|idx| re[idx] as f64 * re[idx] as f64 + im[idx] as f64 * im[idx] as f64)
+            .collect()
+    }
+}
+
 fn prepare_hamming_window(size: usize, scale: f32) -> Vec<f32> {
     let mut result = vec![0.0; size];
 
     for idx in 0..size {
-        result[idx] = scale * (0.54 - 0.46 * (idx as f32 * 2.0 * PI / (size as f32 - 1.0)).cos())
+        result[idx] =
+            scale * (0.54 - 0.46 * math::cosf(idx as f32 * 2.0 * PI / (size as f32 - 1.0)))
     }
 
     result
@@ -70,7 +164,8 @@ fn prepare_hamming_window(size: usize, scale: f32) -> Vec<f32> {
 
 #[cfg(test)]
 mod tests {
-    use super::{prepare_hamming_window, Fft, FRAME_SIZE};
+    use super::{prepare_hamming_window, Fft, Plan};
+    use fingerprinter::{FRAME_OVERLAP, FRAME_SIZE};
     use std::error::Error;
     use std::path::PathBuf;
     use test_data;
@@ -108,6 +203,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_power_spectrum() {
+        let frame: Vec<f32> = (0..64)
+            .map(|idx| ((idx * 37 % 64) as f32 - 32.0) / 8.0)
+            .collect();
+
+        let spectrum = Plan::new(frame.len()).power_spectrum(&frame);
+
+        assert_eq!(33, spectrum.len());
+        for (k, power) in spectrum.iter().enumerate() {
+            let (re, im) = frame
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (n, &x)| {
+                    let angle = -2.0 * ::std::f64::consts::PI * (k * n) as f64 / 64.0;
+                    (re + x as f64 * angle.cos(), im + x as f64 * angle.sin())
+                });
+
+            assert_relative_eq!(
+                re * re + im * im,
+                *power,
+                max_relative = 1e-4,
+                epsilon = 1e-6
+            );
+        }
+    }
+
     #[test]
     fn test_fft() -> Result<(), Box<dyn Error>> {
         let samples = load_audio_file(
@@ -115,7 +237,7 @@ mod tests {
                 .join("./test_data/test_stero_44100_resampled_11025.raw"),
         )?;
 
-        let mut fft = Fft::new();
+        let mut fft = Fft::new(FRAME_SIZE, FRAME_OVERLAP);
         let mut frames = Vec::new();
         fft.consume(&samples, |frame| {
             frames.push(frame);
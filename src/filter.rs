@@ -1,5 +1,7 @@
+use math;
 use rolling_integral_image::RollingIntegralImage;
 
+#[derive(Clone)]
 pub struct Filter {
     type_id: u8,
     y: usize,
@@ -17,6 +19,11 @@ impl Filter {
         }
     }
 
+    /// The number of consecutive chroma frames the filter covers.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
     pub fn apply(&self, image: &RollingIntegralImage, x: usize) -> f64 {
         let (a, b) = match self.type_id {
             0 => filter0(image, x, self.y, self.width, self.height),
@@ -33,7 +40,7 @@ impl Filter {
 }
 
 fn subtract_log(a: f64, b: f64) -> f64 {
-    ((1.0 + a) / (1.0 + b)).ln()
+    math::log((1.0 + a) / (1.0 + b))
 }
 
 fn filter0(image: &RollingIntegralImage, x: usize, y: usize, w: usize, h: usize) -> (f64, f64) {
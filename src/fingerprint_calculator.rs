@@ -1,8 +1,10 @@
+use alloc::vec::Vec;
 use classifiers::Classifiers;
 use rolling_integral_image::RollingIntegralImage;
 
 const FILTER_WIDTH: usize = 16;
 
+#[derive(Clone)]
 pub struct FingerprintCalculator {
     classifiers: Classifiers,
     image: RollingIntegralImage,
@@ -1,6 +1,15 @@
+use alloc::vec::Vec;
 use bit_writer::BitWriter;
+use error::ChromaprintError;
+
+/// The largest number of items which can be stored in the header.
+const MAX_LENGTH: usize = (1 << 24) - 1;
+
+pub fn compress(fingerprint: &[u32], algorithm: u8) -> Result<Vec<u8>, ChromaprintError> {
+    if fingerprint.len() > MAX_LENGTH {
+        return Err(ChromaprintError::FingerprintTooLong(fingerprint.len()));
+    }
 
-pub fn compress(fingerprint: &[u32], algorithm: u8) -> Vec<u8> {
     let mut normal_bits = Vec::new();
     let mut exceptional_bits = Vec::new();
 
@@ -24,18 +33,12 @@ pub fn compress(fingerprint: &[u32], algorithm: u8) -> Vec<u8> {
     let exceptional_bits_size = (exceptional_bits.len() * 5 + 7) / 8;
     let output_size = header_size + normal_bits_size + exceptional_bits_size;
 
-    let mut output = vec![0u8; output_size];
-    write_header(&mut output[..4], fingerprint.len(), algorithm);
-    let normal_bits_output_size = BitWriter::write_all_into(&normal_bits, 3, &mut output[4..]);
-    let exceptional_bits_output_size = BitWriter::write_all_into(
-        &exceptional_bits,
-        5,
-        &mut output[(4 + normal_bits_output_size)..],
-    );
-
-    output.truncate(header_size + exceptional_bits_output_size + normal_bits_output_size);
+    let mut output = Vec::with_capacity(output_size);
+    write_header(&mut output, fingerprint.len(), algorithm);
+    BitWriter::write_all_into(&normal_bits, 3, &mut output);
+    BitWriter::write_all_into(&exceptional_bits, 5, &mut output);
 
-    output
+    Ok(output)
 }
 
 const K_NORMAL_BITS: u8 = 3;
@@ -65,44 +68,55 @@ fn process_subfingerprint(mut x: u32, normal_bits: &mut Vec<u8>, exceptional_bit
     normal_bits.push(0);
 }
 
-fn write_header(output: &mut [u8], size: usize, algorithm: u8) {
-    output[0] = algorithm;
-    output[1] = ((size >> 16) & 255 as usize) as u8;
-    output[2] = ((size >> 8) & 255 as usize) as u8;
-    output[3] = (size & 255 as usize) as u8;
+fn write_header(output: &mut Vec<u8>, size: usize, algorithm: u8) {
+    output.push(algorithm);
+    output.push(((size >> 16) & 255) as u8);
+    output.push(((size >> 8) & 255) as u8);
+    output.push((size & 255) as u8);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::compress;
+    use super::{compress, MAX_LENGTH};
+    use error::ChromaprintError;
 
     #[test]
     fn one_item_one_bit() {
-        assert_eq!(compress(&[1], 0), [0, 0, 0, 1, 1]);
+        assert_eq!(compress(&[1], 0), Ok(vec![0, 0, 0, 1, 1]));
     }
 
     #[test]
     fn one_item_three_bits() {
-        assert_eq!(compress(&[7], 0), [0, 0, 0, 1, 73, 0]);
+        assert_eq!(compress(&[7], 0), Ok(vec![0, 0, 0, 1, 73, 0]));
     }
 
     #[test]
     fn one_item_one_bit_except() {
-        assert_eq!(compress(&[1 << 6], 0), [0, 0, 0, 1, 7, 0]);
+        assert_eq!(compress(&[1 << 6], 0), Ok(vec![0, 0, 0, 1, 7, 0]));
     }
 
     #[test]
     fn one_item_one_bit_except_2() {
-        assert_eq!(compress(&[1 << 8], 0), [0, 0, 0, 1, 7, 2]);
+        assert_eq!(compress(&[1 << 8], 0), Ok(vec![0, 0, 0, 1, 7, 2]));
     }
 
     #[test]
     fn two_items() {
-        assert_eq!(compress(&[1, 0], 0), [0, 0, 0, 2, 65, 0]);
+        assert_eq!(compress(&[1, 0], 0), Ok(vec![0, 0, 0, 2, 65, 0]));
     }
 
     #[test]
     fn two_items_no_change() {
-        assert_eq!(compress(&[1, 1], 0), [0, 0, 0, 2, 1, 0]);
+        assert_eq!(compress(&[1, 1], 0), Ok(vec![0, 0, 0, 2, 1, 0]));
+    }
+
+    #[test]
+    fn too_long() {
+        let fingerprint = vec![0; MAX_LENGTH + 1];
+
+        assert_eq!(
+            compress(&fingerprint, 0),
+            Err(ChromaprintError::FingerprintTooLong(MAX_LENGTH + 1))
+        );
     }
 }
@@ -0,0 +1,200 @@
+use algorithm::Algorithm;
+use alloc::vec::Vec;
+use bit_reader::BitReader;
+use error::ChromaprintError;
+
+const K_NORMAL_BITS: u8 = 3;
+const K_EXCEPTIONAL_BITS: u8 = 5;
+const MAX_NORMAL_VALUE: u8 = (1 << K_NORMAL_BITS) - 1;
+
+/// Reverses `fingerprint_compressor::compress`.
+pub fn decompress(input: &[u8]) -> Result<(Vec<u32>, Algorithm), ChromaprintError> {
+    if input.len() < 4 {
+        return Err(ChromaprintError::TruncatedFingerprint);
+    }
+
+    let algorithm =
+        Algorithm::from_id(input[0]).ok_or(ChromaprintError::UnknownAlgorithm(input[0]))?;
+    let size = ((input[1] as usize) << 16) | ((input[2] as usize) << 8) | (input[3] as usize);
+    if size == 0 {
+        return Ok((Vec::new(), algorithm));
+    }
+
+    // The size in the header isn't trusted when allocating, as each item takes at least one
+    // normal value of the input.
+    let capacity = size.min((input.len() - 4) * 8 / K_NORMAL_BITS as usize);
+
+    let mut normal_reader = BitReader::new(&input[4..]);
+    let mut bits = Vec::with_capacity(capacity);
+    let mut found_values = 0;
+    while found_values < size {
+        let bit = normal_reader
+            .read(K_NORMAL_BITS)
+            .ok_or(ChromaprintError::TruncatedFingerprint)?;
+        if bit == 0 {
+            found_values += 1;
+        }
+
+        bits.push(bit);
+    }
+
+    let mut exceptional_reader = BitReader::new(&input[(4 + normal_reader.bytes_read())..]);
+    for bit in bits.iter_mut() {
+        if *bit == MAX_NORMAL_VALUE {
+            *bit += exceptional_reader
+                .read(K_EXCEPTIONAL_BITS)
+                .ok_or(ChromaprintError::TruncatedFingerprint)?;
+        }
+    }
+
+    let mut fingerprint = Vec::with_capacity(capacity);
+    let mut value = 0u32;
+    let mut last_bit = 0u32;
+    for bit in bits {
+        if bit == 0 {
+            let previous = fingerprint.last().cloned().unwrap_or(0);
+            fingerprint.push(value ^ previous);
+
+            value = 0;
+            last_bit = 0;
+            continue;
+        }
+
+        last_bit += bit as u32;
+        if last_bit > 32 {
+            return Err(ChromaprintError::CorruptFingerprint);
+        }
+
+        value |= 1 << (last_bit - 1);
+    }
+
+    Ok((fingerprint, algorithm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompress;
+    use algorithm::Algorithm;
+    use error::ChromaprintError;
+    use fingerprint_compressor::compress;
+
+    #[test]
+    fn one_item_one_bit() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 1, 1]),
+            Ok((vec![1], Algorithm::Test1))
+        );
+    }
+
+    #[test]
+    fn one_item_three_bits() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 1, 73, 0]),
+            Ok((vec![7], Algorithm::Test1))
+        );
+    }
+
+    #[test]
+    fn one_item_one_bit_except() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 1, 7, 0]),
+            Ok((vec![1 << 6], Algorithm::Test1))
+        );
+    }
+
+    #[test]
+    fn one_item_one_bit_except_2() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 1, 7, 2]),
+            Ok((vec![1 << 8], Algorithm::Test1))
+        );
+    }
+
+    #[test]
+    fn two_items() {
+        assert_eq!(
+            decompress(&[1, 0, 0, 2, 65, 0]),
+            Ok((vec![1, 0], Algorithm::Test2))
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(decompress(&[4, 0, 0, 0]), Ok((vec![], Algorithm::Test5)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut state = 0x1234_5678u32;
+        let fingerprint: Vec<u32> = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect();
+
+        let compressed = compress(&fingerprint, 2).unwrap();
+        assert_eq!(decompress(&compressed), Ok((fingerprint, Algorithm::Test3)));
+    }
+
+    #[test]
+    fn round_trip_high_bits() {
+        let fingerprint = vec![1 << 31, u32::MAX, 0, 1 << 31 | 1, 0x8000_ffff];
+
+        let compressed = compress(&fingerprint, 1).unwrap();
+        assert_eq!(decompress(&compressed), Ok((fingerprint, Algorithm::Test2)));
+    }
+
+    #[test]
+    fn truncated_header() {
+        assert_eq!(
+            decompress(&[1, 0, 0]),
+            Err(ChromaprintError::TruncatedFingerprint)
+        );
+    }
+
+    #[test]
+    fn truncated_normal_bits() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 3, 65]),
+            Err(ChromaprintError::TruncatedFingerprint)
+        );
+    }
+
+    #[test]
+    fn oversized_header() {
+        assert_eq!(
+            decompress(&[1, 0xff, 0xff, 0xff]),
+            Err(ChromaprintError::TruncatedFingerprint)
+        );
+    }
+
+    #[test]
+    fn truncated_exceptional_bits() {
+        assert_eq!(
+            decompress(&[0, 0, 0, 1, 7]),
+            Err(ChromaprintError::TruncatedFingerprint)
+        );
+    }
+
+    #[test]
+    fn unknown_algorithm() {
+        assert_eq!(
+            decompress(&[9, 0, 0, 1, 1]),
+            Err(ChromaprintError::UnknownAlgorithm(9))
+        );
+    }
+
+    #[test]
+    fn bit_out_of_range() {
+        // Five exceptional values of 31 place a bit past the 32nd.
+        let mut input = vec![0, 0, 0, 1];
+        input.extend_from_slice(&[0xff, 0xff, 0x00]);
+        input.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff]);
+
+        assert_eq!(
+            decompress(&input),
+            Err(ChromaprintError::CorruptFingerprint)
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use compare::{compare, Similarity};
+
+/// The number of most significant bits of each item used as the key of the index. The least
+/// significant bits are the ones most likely to flip in noisy recordings.
+const HASH_BITS: u32 = 28;
+
+pub fn hash(item: u32) -> u32 {
+    item >> (32 - HASH_BITS)
+}
+
+/// A track in the index which shares items with a query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub track_id: u32,
+
+    /// The offset of the query in the track, with the same convention as `Fingerprint::compare`.
+    pub offset: isize,
+
+    /// The number of query items whose hashes were found in the track at `offset`.
+    pub hits: usize,
+
+    /// How closely the query matches the track at `offset`.
+    pub similarity: Similarity,
+}
+
+/// An inverted index from item hashes to the tracks and positions they appear at, to find the
+/// tracks which contain a query without comparing it with each of them.
+#[derive(Default)]
+pub struct FingerprintIndex {
+    tracks: HashMap<u32, Vec<u32>>,
+    postings: HashMap<u32, Vec<(u32, u32)>>,
+}
+
+impl FingerprintIndex {
+    pub fn new() -> FingerprintIndex {
+        FingerprintIndex::default()
+    }
+
+    /// Adds the raw fingerprint of a track, replacing the track if it is already indexed.
+    pub fn insert(&mut self, track_id: u32, fingerprint: &[u32]) {
+        self.remove(track_id);
+
+        for (position, item) in fingerprint.iter().enumerate() {
+            self.postings
+                .entry(hash(*item))
+                .or_default()
+                .push((track_id, position as u32));
+        }
+
+        self.tracks.insert(track_id, fingerprint.to_vec());
+    }
+
+    /// Removes a track from the index.
+    ///
+    /// # Returns
+    /// Whether the track was indexed.
+    pub fn remove(&mut self, track_id: u32) -> bool {
+        let fingerprint = match self.tracks.remove(&track_id) {
+            Some(fingerprint) => fingerprint,
+            None => return false,
+        };
+
+        let mut hashes: Vec<u32> = fingerprint.iter().map(|item| hash(*item)).collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        for hash in hashes {
+            if let Entry::Occupied(mut entry) = self.postings.entry(hash) {
+                entry.get_mut().retain(|&(id, _)| id != track_id);
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn contains(&self, track_id: u32) -> bool {
+        self.tracks.contains_key(&track_id)
+    }
+
+    /// The raw fingerprint a track was indexed with.
+    pub fn get(&self, track_id: u32) -> Option<&[u32]> {
+        self.tracks
+            .get(&track_id)
+            .map(|fingerprint| &fingerprint[..])
+    }
+
+    /// The number of indexed tracks.
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Finds the tracks which share the most item hashes with `query` at a consistent offset.
+    ///
+    /// # Returns
+    /// Up to `max_candidates` tracks, with the most hits first.
+    pub fn search(&self, query: &[u32], max_candidates: usize) -> Vec<Candidate> {
+        let mut counts: HashMap<(u32, isize), usize> = HashMap::new();
+        for (query_position, item) in query.iter().enumerate() {
+            let postings = match self.postings.get(&hash(*item)) {
+                Some(postings) => postings,
+                None => continue,
+            };
+
+            for &(track_id, position) in postings {
+                let offset = position as isize - query_position as isize;
+                *counts.entry((track_id, offset)).or_insert(0) += 1;
+            }
+        }
+
+        let mut candidates: Vec<Candidate> = best_offsets(counts)
+            .into_iter()
+            .filter_map(|(track_id, (offset, hits))| {
+                let similarity = compare(&self.tracks[&track_id], query, offset)?;
+                Some(Candidate {
+                    track_id,
+                    offset,
+                    hits,
+                    similarity,
+                })
+            })
+            .collect();
+
+        rank(&mut candidates, max_candidates);
+
+        candidates
+    }
+}
+
+/// Picks the offset with the most hits for each track, preferring the smallest offset on ties.
+pub fn best_offsets(counts: HashMap<(u32, isize), usize>) -> HashMap<u32, (isize, usize)> {
+    let mut best: HashMap<u32, (isize, usize)> = HashMap::new();
+    for ((track_id, offset), hits) in counts {
+        let entry = best.entry(track_id).or_insert((offset, hits));
+        if hits > entry.1 || (hits == entry.1 && offset < entry.0) {
+            *entry = (offset, hits);
+        }
+    }
+
+    best
+}
+
+/// Orders candidates by hits then bit errors and keeps the first `max_candidates`.
+pub fn rank(candidates: &mut Vec<Candidate>, max_candidates: usize) {
+    candidates.sort_by(|a, b| {
+        b.hits
+            .cmp(&a.hits)
+            .then(a.similarity.bit_errors.cmp(&b.similarity.bit_errors))
+            .then(a.track_id.cmp(&b.track_id))
+    });
+    candidates.truncate(max_candidates);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FingerprintIndex;
+
+    fn random_fingerprint(length: usize, seed: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect()
+    }
+
+    fn index_tracks(count: u32) -> FingerprintIndex {
+        let mut index = FingerprintIndex::new();
+        for track_id in 0..count {
+            index.insert(track_id, &random_fingerprint(500, track_id + 100));
+        }
+
+        index
+    }
+
+    #[test]
+    fn test_search() {
+        let index = index_tracks(20);
+        let query: Vec<u32> = random_fingerprint(500, 107)[200..300]
+            .iter()
+            .map(|item| item ^ 0x3)
+            .collect();
+
+        let candidates = index.search(&query, 5);
+
+        assert_eq!(1, candidates.len());
+        assert_eq!(7, candidates[0].track_id);
+        assert_eq!(200, candidates[0].offset);
+        assert_eq!(100, candidates[0].hits);
+        assert_eq!(100, candidates[0].similarity.overlap);
+        assert_eq!(200, candidates[0].similarity.bit_errors);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut index = index_tracks(3);
+        let track = random_fingerprint(500, 101);
+        let mut partial = random_fingerprint(500, 200);
+        partial[300..350].copy_from_slice(&track[0..50]);
+        index.insert(10, &partial);
+
+        let candidates = index.search(&track[0..100], 1);
+
+        assert_eq!(1, candidates.len());
+        assert_eq!(1, candidates[0].track_id);
+
+        let candidates = index.search(&track[0..100], 5);
+
+        assert_eq!(2, candidates.len());
+        assert_eq!(10, candidates[1].track_id);
+        assert_eq!(300, candidates[1].offset);
+        assert_eq!(50, candidates[1].hits);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut index = index_tracks(5);
+        let query = random_fingerprint(500, 102);
+
+        assert_eq!(5, index.len());
+        assert!(index.remove(2));
+        assert!(!index.remove(2));
+        assert!(!index.contains(2));
+        assert_eq!(4, index.len());
+        assert!(index.search(&query, 5).is_empty());
+
+        index.insert(2, &query[..100]);
+        index.insert(2, &query[100..]);
+
+        assert_eq!(Some(&query[100..]), index.get(2));
+        let candidates = index.search(&query, 5);
+        assert_eq!(1, candidates.len());
+        assert_eq!(-100, candidates[0].offset);
+    }
+}
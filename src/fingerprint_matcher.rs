@@ -0,0 +1,307 @@
+use fingerprinter::Fingerprint;
+use gaussian_filter::{gaussian_filter, gradient};
+
+/// The number of most significant bits of each item used to find candidate offsets.
+const ALIGN_BITS: u32 = 12;
+
+/// The largest average number of differing bits per item for a segment to be considered a
+/// match.
+const DEFAULT_MATCH_THRESHOLD: f64 = 10.0;
+
+/// Segments next to each other with average bit errors closer than this are merged.
+const MERGE_THRESHOLD: f64 = 0.7;
+
+/// The smallest change in the smoothed bit errors which is considered the boundary of a segment.
+const GRADIENT_THRESHOLD: f64 = 0.15;
+
+/// A range of items which match between two fingerprints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    /// The position of the first item of the segment in the first fingerprint.
+    pub start1: usize,
+
+    /// The position of the first item of the segment in the second fingerprint.
+    pub start2: usize,
+
+    /// The number of items in the segment.
+    pub length: usize,
+
+    /// The fraction of bits which match in the segment, between 0 and 1.
+    pub score: f64,
+
+    /// The duration of audio in seconds between consecutive items.
+    pub item_duration: f64,
+}
+
+impl Segment {
+    /// The position after the last item of the segment in the first fingerprint.
+    pub fn end1(&self) -> usize {
+        self.start1 + self.length
+    }
+
+    /// The position after the last item of the segment in the second fingerprint.
+    pub fn end2(&self) -> usize {
+        self.start2 + self.length
+    }
+
+    pub fn start1_seconds(&self) -> f64 {
+        self.start1 as f64 * self.item_duration
+    }
+
+    pub fn end1_seconds(&self) -> f64 {
+        self.end1() as f64 * self.item_duration
+    }
+
+    pub fn start2_seconds(&self) -> f64 {
+        self.start2 as f64 * self.item_duration
+    }
+
+    pub fn end2_seconds(&self) -> f64 {
+        self.end2() as f64 * self.item_duration
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.length as f64 * self.item_duration
+    }
+
+    fn bit_errors(&self) -> f64 {
+        (1.0 - self.score) * 32.0
+    }
+
+    /// Joins this segment with the one directly after it.
+    fn merge(&self, next: &Segment) -> Segment {
+        let length = self.length + next.length;
+        let bit_errors = (self.bit_errors() * self.length as f64
+            + next.bit_errors() * next.length as f64)
+            / length as f64;
+
+        Segment {
+            length,
+            score: 1.0 - bit_errors / 32.0,
+            ..*self
+        }
+    }
+}
+
+/// Finds the parts of two fingerprints which match, like the matcher of upstream chromaprint.
+pub struct FingerprintMatcher {
+    match_threshold: f64,
+}
+
+impl FingerprintMatcher {
+    pub fn new() -> FingerprintMatcher {
+        FingerprintMatcher {
+            match_threshold: DEFAULT_MATCH_THRESHOLD,
+        }
+    }
+
+    /// Sets the largest average number of differing bits per item, out of 32, for a part of the
+    /// fingerprints to be considered a match.
+    pub fn set_match_threshold(&mut self, match_threshold: f64) {
+        self.match_threshold = match_threshold;
+    }
+
+    /// Finds the matching segments of two fingerprints computed with the same algorithm.
+    ///
+    /// The fingerprints are lined up at the offset most of their items agree on. The bit errors
+    /// between the lined up items are smoothed and split where they change sharply, and the
+    /// parts with few errors are returned in order.
+    pub fn match_fingerprints(&self, fp1: &Fingerprint, fp2: &Fingerprint) -> Vec<Segment> {
+        let (fp1, fp2, item_duration) = (fp1.0, fp2.0, fp1.1.item_duration());
+
+        let offset = match best_alignment(fp1, fp2) {
+            Some(offset) => offset,
+            None => return Vec::new(),
+        };
+
+        let offset1 = offset.max(0) as usize;
+        let offset2 = (-offset).max(0) as usize;
+        let bit_errors: Vec<f64> = fp1[offset1..]
+            .iter()
+            .zip(&fp2[offset2..])
+            .map(|(a, b)| (a ^ b).count_ones() as f64)
+            .collect();
+
+        let smoothed = gaussian_filter(&bit_errors, 8.0, 3);
+        let gradient: Vec<f64> = gradient(&smoothed).iter().map(|x| x.abs()).collect();
+
+        let size = bit_errors.len();
+        let mut boundaries = Vec::new();
+        for idx in 1..size.saturating_sub(1) {
+            let value = gradient[idx];
+            let is_peak = value > GRADIENT_THRESHOLD
+                && value >= gradient[idx - 1]
+                && value >= gradient[idx + 1];
+            let follows_peak = boundaries.last().is_some_and(|last| last + 1 >= idx);
+
+            if is_peak && !follows_peak {
+                boundaries.push(idx);
+            }
+        }
+        boundaries.push(size);
+
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut begin = 0;
+        for end in boundaries {
+            let length = end - begin;
+            let average = bit_errors[begin..end].iter().sum::<f64>() / length as f64;
+
+            if average < self.match_threshold {
+                let segment = Segment {
+                    start1: offset1 + begin,
+                    start2: offset2 + begin,
+                    length,
+                    score: 1.0 - average / 32.0,
+                    item_duration,
+                };
+
+                let merged = match segments.last() {
+                    Some(last)
+                        if last.end1() == segment.start1
+                            && (last.bit_errors() - average).abs() < MERGE_THRESHOLD =>
+                    {
+                        Some(last.merge(&segment))
+                    }
+                    _ => None,
+                };
+
+                match merged {
+                    Some(merged) => *segments.last_mut().unwrap() = merged,
+                    None => segments.push(segment),
+                }
+            }
+
+            begin = end;
+        }
+
+        segments
+    }
+}
+
+impl Default for FingerprintMatcher {
+    fn default() -> FingerprintMatcher {
+        FingerprintMatcher::new()
+    }
+}
+
+/// Finds the offset of `fp2` in `fp1` shared by the most pairs of items with equal hashes. Only
+/// offsets which are local peaks of the histogram and shared by more than one pair qualify.
+fn best_alignment(fp1: &[u32], fp2: &[u32]) -> Option<isize> {
+    let mut hashes: Vec<(u32, bool, usize)> = fp1
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| (item >> (32 - ALIGN_BITS), false, idx))
+        .chain(
+            fp2.iter()
+                .enumerate()
+                .map(|(idx, item)| (item >> (32 - ALIGN_BITS), true, idx)),
+        )
+        .collect();
+    hashes.sort();
+
+    // Offsets are shifted by the length of `fp2` so they aren't negative. Within each run of
+    // equal hashes the items of `fp1` sort before those of `fp2`, so only the pairs across the
+    // two fingerprints are visited.
+    let mut histogram = vec![0u32; fp1.len() + fp2.len()];
+    for run in hashes.chunk_by(|a, b| a.0 == b.0) {
+        let fp1_count = run
+            .iter()
+            .take_while(|&&(_, from_fp2, _)| !from_fp2)
+            .count();
+        let (items1, items2) = run.split_at(fp1_count);
+
+        for &(_, _, idx1) in items1 {
+            for &(_, _, idx2) in items2 {
+                histogram[idx1 + fp2.len() - idx2] += 1;
+            }
+        }
+    }
+
+    let mut best: Option<(u32, usize)> = None;
+    for (idx, &count) in histogram.iter().enumerate() {
+        let is_peak_left = idx == 0 || histogram[idx - 1] <= count;
+        let is_peak_right = idx + 1 == histogram.len() || histogram[idx + 1] <= count;
+
+        if count > 1 && is_peak_left && is_peak_right && best.is_none_or(|(c, _)| count > c) {
+            best = Some((count, idx));
+        }
+    }
+
+    best.map(|(_, idx)| idx as isize - fp2.len() as isize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_alignment, FingerprintMatcher};
+    use algorithm::Algorithm;
+    use fingerprinter::Fingerprint;
+
+    fn random_fingerprint(length: usize, seed: u32) -> Vec<u32> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_best_alignment() {
+        let fp1 = random_fingerprint(300, 1);
+        let fp2 = fp1[120..220].to_vec();
+
+        assert_eq!(Some(120), best_alignment(&fp1, &fp2));
+        assert_eq!(Some(-120), best_alignment(&fp2, &fp1));
+        assert_eq!(None, best_alignment(&fp1, &[]));
+    }
+
+    #[test]
+    fn test_best_alignment_repeated_items() {
+        // Repeated items share a hash, and pairs of them within `fp2` mustn't count as offsets.
+        let fp1 = random_fingerprint(300, 1);
+        let mut fp2 = fp1[100..200].to_vec();
+        fp2.extend(vec![0xdead_beef; 150]);
+
+        assert_eq!(Some(100), best_alignment(&fp1, &fp2));
+    }
+
+    #[test]
+    fn test_match_embedded_segment() {
+        // A track which is played part way into a mix, with a few bits flipped.
+        let track = random_fingerprint(400, 2);
+        let mut mix = random_fingerprint(200, 3);
+        mix.extend(track[50..350].iter().map(|item| item ^ 0x0001_0001));
+        mix.extend(random_fingerprint(200, 4));
+
+        let segments = FingerprintMatcher::new().match_fingerprints(
+            &Fingerprint(&mix, Algorithm::Test2),
+            &Fingerprint(&track, Algorithm::Test2),
+        );
+
+        assert_eq!(1, segments.len());
+        let segment = segments[0];
+        assert!((segment.start1 as isize - 200).abs() <= 2);
+        assert!((segment.end1() as isize - 500).abs() <= 2);
+        assert_eq!(segment.start1 - 150, segment.start2);
+        assert!(segment.score > 0.9);
+        assert_abs_diff_eq!(
+            segment.length as f64 * Algorithm::Test2.item_duration(),
+            segment.duration_seconds(),
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let fp1 = random_fingerprint(300, 5);
+        let fp2 = random_fingerprint(300, 6);
+
+        let segments = FingerprintMatcher::new().match_fingerprints(
+            &Fingerprint(&fp1, Algorithm::Test2),
+            &Fingerprint(&fp2, Algorithm::Test2),
+        );
+
+        assert!(segments.is_empty());
+    }
+}
@@ -1,97 +1,352 @@
+use algorithm::Algorithm;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
 use audio_processor::AudioProcessor;
 use chroma::Chroma;
-use chroma_filter::{ChromaFilter, FILTER_COEFFICIENTS};
+use chroma_filter::ChromaFilter;
 use chroma_normalize::normalize_vector;
-use classifiers;
+use compare::{self, Similarity};
+use core::mem;
+#[cfg(feature = "std")]
+use core::str::FromStr;
+#[cfg(feature = "std")]
 use encode;
+use error::ChromaprintError;
 use fft::Fft;
 use fingerprint_calculator::FingerprintCalculator;
 use fingerprint_compressor;
+use fingerprint_decompressor;
+use sample::Sample;
 
-pub const TARGET_SAMPLE_RATE: u16 = 11025;
+pub const TARGET_SAMPLE_RATE: u32 = 11025;
 pub const MIN_FREQ: u32 = 28;
 pub const MAX_FREQ: u32 = 3520;
 pub const FRAME_SIZE: usize = 4096;
+pub const FRAME_OVERLAP: usize = FRAME_SIZE - FRAME_SIZE / 3;
 
+/// Computes the fingerprint of a stream of audio.
+///
+/// Clones share the tables computed when the fingerprinter is created, so cloning an unused
+/// fingerprinter is a cheap way to make another for audio in the same format.
+#[derive(Clone)]
 pub struct Fingerprinter {
-    audio_processor: Option<AudioProcessor>,
-    fft: Option<Fft>,
-    chroma: Chroma,
-    chroma_filter: ChromaFilter,
-    fingerprint_calculator: FingerprintCalculator,
+    algorithm: Algorithm,
+    audio_processor: AudioProcessor,
+    pipeline: FingerprintPipeline,
+    started: bool,
+    finished: bool,
 }
 
 impl Fingerprinter {
-    pub fn new(sample_rate: u16) -> Fingerprinter {
-        Fingerprinter {
-            audio_processor: Some(AudioProcessor::new(TARGET_SAMPLE_RATE, sample_rate)),
-            fft: Some(Fft::new()),
-            chroma: Chroma::new(
-                MIN_FREQ,
-                MAX_FREQ,
-                FRAME_SIZE as u32,
-                TARGET_SAMPLE_RATE as u32,
-            ),
-            chroma_filter: ChromaFilter::new(&FILTER_COEFFICIENTS),
-            fingerprint_calculator: FingerprintCalculator::new(
-                classifiers::get_default_classifier(),
-            ),
+    /// Creates a fingerprinter for audio with `channels` interleaved channels sampled at
+    /// `sample_rate`.
+    pub fn new(sample_rate: u32, channels: u16) -> Result<Fingerprinter, ChromaprintError> {
+        Fingerprinter::with_algorithm(sample_rate, channels, Algorithm::default())
+    }
+
+    pub fn with_algorithm(
+        sample_rate: u32,
+        channels: u16,
+        algorithm: Algorithm,
+    ) -> Result<Fingerprinter, ChromaprintError> {
+        let configuration = algorithm.configuration();
+
+        let mut audio_processor = AudioProcessor::new(TARGET_SAMPLE_RATE, sample_rate, channels)?;
+        if let Some(silence_removal) = configuration.silence_removal {
+            audio_processor.remove_silence(silence_removal.threshold, silence_removal.window);
         }
+
+        Ok(Fingerprinter {
+            algorithm,
+            audio_processor,
+            pipeline: FingerprintPipeline {
+                fft: Fft::new(configuration.frame_size, configuration.frame_overlap),
+                chroma: Chroma::new(
+                    MIN_FREQ,
+                    MAX_FREQ,
+                    configuration.frame_size as u32,
+                    TARGET_SAMPLE_RATE,
+                    configuration.interpolate,
+                ),
+                chroma_filter: ChromaFilter::new(configuration.filter_coefficients),
+                fingerprint_calculator: FingerprintCalculator::new(configuration.classifiers),
+                chroma_recorder: None,
+            },
+            started: false,
+            finished: false,
+        })
     }
 
-    pub fn feed(&mut self, raw_pcm: &[i16]) {
-        let mut audio_processor = self.audio_processor.take().unwrap();
-        let mut fft = self.fft.take().unwrap();
+    /// Strips the silence at the start of the audio before fingerprinting it. Audio is considered
+    /// silent until the average absolute amplitude over `window` samples of the audio resampled
+    /// to 11025 Hz exceeds `threshold`.
+    ///
+    /// # Errors
+    /// `AlreadyStarted` once audio has been fed, as the silence would be removed from the middle of
+    /// the stream.
+    pub fn remove_silence(
+        &mut self,
+        threshold: u16,
+        window: usize,
+    ) -> Result<(), ChromaprintError> {
+        self.check_not_started()?;
+        self.audio_processor.remove_silence(threshold, window);
 
-        audio_processor.feed(raw_pcm, |samples| {
-            self.handle_resampled(samples, &mut fft);
+        Ok(())
+    }
+
+    /// Keeps the chroma features of each frame so they can be read with `chroma_features` or
+    /// `take_chroma_features`.
+    ///
+    /// # Errors
+    /// `AlreadyStarted` once audio has been fed, as the rows recorded wouldn't line up with the
+    /// sub-fingerprints.
+    pub fn record_chroma_features(&mut self) -> Result<(), ChromaprintError> {
+        self.check_not_started()?;
+        self.pipeline.chroma_recorder = Some(ChromaRecorder {
+            item_duration: self.algorithm.item_duration(),
+            pending: VecDeque::new(),
+            rows: Vec::new(),
+            taken: 0,
         });
 
-        self.fft = Some(fft);
-        self.audio_processor = Some(audio_processor);
+        Ok(())
     }
 
-    pub fn finish(&mut self) {
-        let mut fft = self.fft.take().unwrap();
+    /// Consumes interleaved samples in any supported format. The number of samples doesn't need
+    /// to be a multiple of the number of channels.
+    pub fn feed<S: Sample>(&mut self, raw_pcm: &[S]) -> Result<(), ChromaprintError> {
+        if self.finished {
+            return Err(ChromaprintError::AlreadyFinished);
+        }
 
+        self.started = true;
+        let raw_pcm = S::to_i16_slice(raw_pcm);
+        let pipeline = &mut self.pipeline;
         self.audio_processor
-            .as_mut()
-            .unwrap()
-            .flush()
-            .map(|last_samples| self.handle_resampled(last_samples, &mut fft));
+            .feed(&raw_pcm, |samples| pipeline.handle_resampled(&samples));
+
+        Ok(())
+    }
+
+    /// Processes any buffered audio. No more audio can be fed afterwards.
+    pub fn finish(&mut self) -> Result<(), ChromaprintError> {
+        if self.finished {
+            return Err(ChromaprintError::AlreadyFinished);
+        }
+
+        let pipeline = &mut self.pipeline;
+        self.audio_processor
+            .flush(|last_samples| pipeline.handle_resampled(&last_samples));
+        self.finished = true;
+
+        Ok(())
+    }
+
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(
+            self.pipeline.fingerprint_calculator.fingerprint(),
+            self.algorithm,
+        )
+    }
 
-        self.fft = Some(fft);
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
     }
 
-    fn handle_resampled(&mut self, samples: Vec<i16>, fft: &mut Fft) {
-        fft.consume(&samples, |frame| {
-            let features = self.chroma.handle_frame(&frame);
-            if let Some(filtered) = self.chroma_filter.handle_features(features) {
+    /// Checks that the fingerprinter can still be configured.
+    fn check_not_started(&self) -> Result<(), ChromaprintError> {
+        if self.finished {
+            Err(ChromaprintError::AlreadyFinished)
+        } else if self.started {
+            Err(ChromaprintError::AlreadyStarted)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The chroma features recorded so far, or nothing unless `record_chroma_features` was
+    /// called. Row `i` is row `i` of the image the sub-fingerprints are computed from, so
+    /// sub-fingerprint `i` is computed from rows `i` to `i + 15`. Rows removed by
+    /// `take_chroma_features` are left out.
+    pub fn chroma_features(&self) -> &[ChromaFeatures] {
+        match self.pipeline.chroma_recorder {
+            Some(ref recorder) => &recorder.rows,
+            None => &[],
+        }
+    }
+
+    /// Removes and returns the chroma features recorded since they were last taken, so long
+    /// streams can be read without keeping every row. The timestamps still count from the start
+    /// of the audio.
+    pub fn take_chroma_features(&mut self) -> Vec<ChromaFeatures> {
+        match self.pipeline.chroma_recorder {
+            Some(ref mut recorder) => {
+                recorder.taken += recorder.rows.len();
+                mem::take(&mut recorder.rows)
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The chroma features of a frame of audio. Each array holds the energy of the 12 notes of the
+/// octave, starting at A.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChromaFeatures {
+    /// The start of the frame in seconds from the start of the audio, after any silence removed
+    /// from the start.
+    pub timestamp: f64,
+
+    /// The features of the frame.
+    pub raw: [f64; 12],
+
+    /// The features of the frame and the frames following it, blurred over time by the chroma
+    /// filter of the algorithm.
+    pub filtered: [f64; 12],
+
+    /// `filtered` scaled to unit length, or zeros when it is close to silent. These are the rows
+    /// the sub-fingerprints are computed from.
+    pub normalized: [f64; 12],
+}
+
+/// The stages of the pipeline which run on resampled audio.
+#[derive(Clone)]
+struct FingerprintPipeline {
+    fft: Fft,
+    chroma: Chroma,
+    chroma_filter: ChromaFilter,
+    fingerprint_calculator: FingerprintCalculator,
+    chroma_recorder: Option<ChromaRecorder>,
+}
+
+impl FingerprintPipeline {
+    fn handle_resampled(&mut self, samples: &[i16]) {
+        let chroma = &self.chroma;
+        let chroma_filter = &mut self.chroma_filter;
+        let fingerprint_calculator = &mut self.fingerprint_calculator;
+        let chroma_recorder = &mut self.chroma_recorder;
+
+        self.fft.consume(samples, |frame| {
+            let features = chroma.handle_frame(&frame);
+            if let Some(ref mut recorder) = *chroma_recorder {
+                recorder.pending.push_back(features);
+            }
+
+            if let Some(filtered) = chroma_filter.handle_features(features) {
                 let normalized_features = normalize_vector(filtered);
-                self.fingerprint_calculator.consume(normalized_features);
+                if let Some(ref mut recorder) = *chroma_recorder {
+                    recorder.record(filtered, normalized_features);
+                }
+                fingerprint_calculator.consume(normalized_features);
             }
         });
     }
+}
 
-    pub fn fingerprint(&self) -> Fingerprint {
-        Fingerprint(self.fingerprint_calculator.fingerprint())
+#[derive(Clone)]
+struct ChromaRecorder {
+    item_duration: f64,
+
+    /// The raw features of the frames the chroma filter hasn't produced a row for yet. The filter
+    /// only produces a row for a frame once it has seen the frames following it.
+    pending: VecDeque<[f64; 12]>,
+
+    rows: Vec<ChromaFeatures>,
+
+    /// The number of rows removed by `take_chroma_features`.
+    taken: usize,
+}
+
+impl ChromaRecorder {
+    fn record(&mut self, filtered: [f64; 12], normalized: [f64; 12]) {
+        let raw = self
+            .pending
+            .pop_front()
+            .expect("the raw features are recorded before they are filtered");
+
+        self.rows.push(ChromaFeatures {
+            timestamp: (self.taken + self.rows.len()) as f64 * self.item_duration,
+            raw,
+            filtered,
+            normalized,
+        });
     }
 }
 
-pub struct Fingerprint<'a>(pub &'a [u32]);
+/// The raw sub-fingerprints and the algorithm used to compute them.
+pub struct Fingerprint<'a>(pub &'a [u32], pub Algorithm);
 
 impl<'a> Fingerprint<'a> {
-    pub fn compress(&self) -> CompressedFingerprint {
-        CompressedFingerprint(fingerprint_compressor::compress(self.0, 1))
+    /// Compares the items of this fingerprint with `other` when `other` starts `offset` items
+    /// into this one. See `compare::compare`.
+    pub fn compare(&self, other: &Fingerprint, offset: isize) -> Option<Similarity> {
+        compare::compare(self.0, other.0, offset)
+    }
+
+    /// Finds the offset at which `other` best lines up with this fingerprint. Only offsets within
+    /// `max_offset` items are considered when it is given.
+    pub fn align(&self, other: &Fingerprint, max_offset: Option<usize>) -> Option<Alignment> {
+        let offset = compare::best_offset(self.0, other.0, max_offset)?;
+        let similarity = self.compare(other, offset)?;
+
+        Some(Alignment {
+            offset,
+            offset_seconds: offset as f64 * self.1.item_duration(),
+            similarity,
+        })
+    }
+
+    pub fn compress(&self) -> Result<CompressedFingerprint, ChromaprintError> {
+        Ok(CompressedFingerprint(fingerprint_compressor::compress(
+            self.0,
+            self.1.id(),
+        )?))
     }
 }
 
+/// Where one fingerprint best lines up with another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Alignment {
+    /// The number of items the second fingerprint starts into the first one. Negative when the
+    /// first fingerprint starts part way into the second one.
+    pub offset: isize,
+
+    /// The offset in seconds.
+    pub offset_seconds: f64,
+
+    /// How well the fingerprints match at the offset.
+    pub similarity: Similarity,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CompressedFingerprint(pub Vec<u8>);
 
 impl CompressedFingerprint {
+    /// Parses a fingerprint encoded by `encode` or by fpcalc.
+    #[cfg(feature = "std")]
+    pub fn decode(encoded: &str) -> Result<CompressedFingerprint, ChromaprintError> {
+        Ok(CompressedFingerprint(encode::decode(encoded)?))
+    }
+
+    #[cfg(feature = "std")]
     pub fn encode(&self) -> String {
         encode::encode(&self.0)
     }
+
+    /// Recovers the raw sub-fingerprints and the algorithm used to compute them.
+    pub fn decompress(&self) -> Result<(Vec<u32>, Algorithm), ChromaprintError> {
+        fingerprint_decompressor::decompress(&self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for CompressedFingerprint {
+    type Err = ChromaprintError;
+
+    fn from_str(encoded: &str) -> Result<CompressedFingerprint, ChromaprintError> {
+        CompressedFingerprint::decode(encoded)
+    }
 }
 
 #[cfg(test)]
@@ -100,19 +355,28 @@ mod tests {
     use std::path::PathBuf;
     use tests;
 
-    use super::Fingerprinter;
+    #[cfg(feature = "std")]
+    use super::CompressedFingerprint;
+    use super::{Fingerprint, Fingerprinter};
+    use algorithm::Algorithm;
+    use chroma_filter::FILTER_COEFFICIENTS;
+    use chroma_normalize::normalize_vector;
+    use error::ChromaprintError;
+    use silence_remover::SILENCE_WINDOW;
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_fingerprinter() -> Result<(), Box<dyn Error>> {
         let samples = tests::load_audio_file(
             &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
         )?;
 
-        let mut fingerprinter = Fingerprinter::new(44100);
-        fingerprinter.feed(&samples);
-        fingerprinter.finish();
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        fingerprinter.feed(&samples)?;
+        fingerprinter.finish()?;
 
-        let fingerprint = fingerprinter.fingerprint().compress().encode();
+        let compressed = fingerprinter.fingerprint().compress()?;
+        let fingerprint = compressed.encode();
 
         // Doesn't exactly match the fingerprint from the C library due to small variances in the
         // FFT library. The fingerprint doesn't need to be an exact match to work with AcoustID.
@@ -121,6 +385,296 @@ mod tests {
             "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog",
         );
 
+        let decoded: CompressedFingerprint = fingerprint.parse()?;
+        assert_eq!(compressed.0, decoded.0);
+
+        let (decompressed, algorithm) = compressed.decompress()?;
+        assert_eq!(fingerprinter.fingerprint().0, &decompressed[..]);
+        assert_eq!(fingerprinter.algorithm(), algorithm);
+
         Ok(())
     }
+
+    #[test]
+    fn test_fingerprinter_algorithms() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+
+        let algorithms = [
+            Algorithm::Test1,
+            Algorithm::Test2,
+            Algorithm::Test3,
+            Algorithm::Test4,
+            Algorithm::Test5,
+        ];
+
+        for algorithm in algorithms.iter() {
+            let mut fingerprinter = Fingerprinter::with_algorithm(44100, 1, *algorithm)?;
+            fingerprinter.feed(&samples)?;
+            fingerprinter.finish()?;
+
+            let fingerprint = fingerprinter.fingerprint();
+            assert!(!fingerprint.0.is_empty());
+            assert_eq!(algorithm.id(), fingerprint.compress()?.0[0]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chroma_features() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        fingerprinter.feed(&samples)?;
+        fingerprinter.finish()?;
+        assert!(fingerprinter.chroma_features().is_empty());
+
+        let mut recording_fingerprinter = Fingerprinter::new(44100, 1)?;
+        recording_fingerprinter.record_chroma_features()?;
+        recording_fingerprinter.feed(&samples)?;
+        recording_fingerprinter.finish()?;
+
+        let fingerprint = recording_fingerprinter.fingerprint().0;
+        let rows = recording_fingerprinter.chroma_features();
+        assert!(!fingerprint.is_empty());
+        assert_eq!(fingerprinter.fingerprint().0, fingerprint);
+        assert_eq!(fingerprint.len() + 15, rows.len());
+
+        let item_duration = Algorithm::default().item_duration();
+        for (idx, row) in rows.iter().enumerate() {
+            assert_ulps_eq!(idx as f64 * item_duration, row.timestamp);
+            assert_eq!(normalize_vector(row.filtered), row.normalized);
+
+            if idx + FILTER_COEFFICIENTS.len() <= rows.len() {
+                for note in 0..12 {
+                    let filtered: f64 = FILTER_COEFFICIENTS
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, coefficient)| coefficient * rows[idx + offset].raw[note])
+                        .sum();
+                    assert_relative_eq!(filtered, row.filtered[note]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_chroma_features() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+
+        let mut expected = Fingerprinter::new(44100, 1)?;
+        expected.record_chroma_features()?;
+        expected.feed(&samples)?;
+        expected.finish()?;
+
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        assert!(fingerprinter.take_chroma_features().is_empty());
+        fingerprinter.record_chroma_features()?;
+
+        let (first_half, second_half) = samples.split_at(samples.len() / 2);
+        fingerprinter.feed(first_half)?;
+        let mut rows = fingerprinter.take_chroma_features();
+        assert!(!rows.is_empty());
+        assert!(fingerprinter.chroma_features().is_empty());
+
+        fingerprinter.feed(second_half)?;
+        fingerprinter.finish()?;
+        assert_eq!(
+            fingerprinter.chroma_features(),
+            &expected.chroma_features()[rows.len()..]
+        );
+        rows.extend(fingerprinter.take_chroma_features());
+
+        assert_eq!(expected.chroma_features(), &rows[..]);
+        assert!(fingerprinter.chroma_features().is_empty());
+
+        assert_eq!(
+            Err(ChromaprintError::AlreadyFinished),
+            fingerprinter.record_chroma_features()
+        );
+
+        let mut started = Fingerprinter::new(44100, 1)?;
+        started.feed(first_half)?;
+        assert_eq!(
+            Err(ChromaprintError::AlreadyStarted),
+            started.record_chroma_features()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_silence() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+        let mut padded_samples = vec![0i16; 44100];
+        padded_samples.extend_from_slice(&samples);
+
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        fingerprinter.remove_silence(50, SILENCE_WINDOW)?;
+        fingerprinter.feed(&samples)?;
+        fingerprinter.finish()?;
+
+        let mut padded_fingerprinter = Fingerprinter::new(44100, 1)?;
+        padded_fingerprinter.remove_silence(50, SILENCE_WINDOW)?;
+        padded_fingerprinter.feed(&padded_samples)?;
+        padded_fingerprinter.finish()?;
+
+        assert_eq!(
+            fingerprinter.fingerprint().0,
+            padded_fingerprinter.fingerprint().0
+        );
+
+        assert_eq!(
+            Err(ChromaprintError::AlreadyFinished),
+            fingerprinter.remove_silence(50, SILENCE_WINDOW)
+        );
+
+        let mut late_fingerprinter = Fingerprinter::new(44100, 1)?;
+        late_fingerprinter.feed(&samples)?;
+        assert_eq!(
+            Err(ChromaprintError::AlreadyStarted),
+            late_fingerprinter.remove_silence(50, SILENCE_WINDOW)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_channel() -> Result<(), Box<dyn Error>> {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw");
+        // The file is too short to produce a fingerprint once downmixed, so it is repeated.
+        let interleaved = tests::load_audio_file(&path)?.repeat(4);
+        let downmixed = tests::load_stero_audio_file(&path)?.repeat(4);
+
+        let mut fingerprinter = Fingerprinter::new(44100, 2)?;
+        for chunk in interleaved.chunks(1001) {
+            fingerprinter.feed(chunk)?;
+        }
+        fingerprinter.finish()?;
+
+        let mut mono_fingerprinter = Fingerprinter::new(44100, 1)?;
+        mono_fingerprinter.feed(&downmixed)?;
+        mono_fingerprinter.finish()?;
+
+        assert!(!fingerprinter.fingerprint().0.is_empty());
+        assert_eq!(
+            fingerprinter.fingerprint().0,
+            mono_fingerprinter.fingerprint().0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_high_sample_rate() -> Result<(), Box<dyn Error>> {
+        let path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw");
+        let samples = tests::load_stero_audio_file(&path)?.repeat(4);
+        let upsampled: Vec<i16> = samples.iter().flat_map(|sample| vec![*sample; 4]).collect();
+
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        fingerprinter.feed(&samples)?;
+        fingerprinter.finish()?;
+
+        let mut upsampled_fingerprinter = Fingerprinter::new(176400, 1)?;
+        upsampled_fingerprinter.feed(&upsampled)?;
+        upsampled_fingerprinter.finish()?;
+
+        let expected = fingerprinter.fingerprint().0;
+        let actual = upsampled_fingerprinter.fingerprint().0;
+        assert_eq!(expected.len(), actual.len());
+
+        let differing_bits: u32 = expected
+            .iter()
+            .zip(actual)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        assert!((differing_bits as usize) < expected.len() * 32 / 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_float_samples() -> Result<(), Box<dyn Error>> {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )?;
+        let float_samples: Vec<f32> = samples
+            .iter()
+            .map(|sample| *sample as f32 / 32768.0)
+            .collect();
+
+        let mut fingerprinter = Fingerprinter::new(44100, 1)?;
+        fingerprinter.feed(&samples)?;
+        fingerprinter.finish()?;
+
+        let mut float_fingerprinter = Fingerprinter::new(44100, 1)?;
+        float_fingerprinter.feed(&float_samples)?;
+        float_fingerprinter.finish()?;
+
+        assert_eq!(
+            fingerprinter.fingerprint().0,
+            float_fingerprinter.fingerprint().0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(
+            Some(ChromaprintError::InvalidSampleRate(0)),
+            Fingerprinter::new(0, 2).err()
+        );
+        assert_eq!(
+            Some(ChromaprintError::InvalidChannelCount(0)),
+            Fingerprinter::new(44100, 0).err()
+        );
+    }
+
+    #[test]
+    fn test_feed_after_finish() -> Result<(), Box<dyn Error>> {
+        let mut fingerprinter = Fingerprinter::new(44100, 2)?;
+        fingerprinter.feed(&[0i16; 1024])?;
+        fingerprinter.finish()?;
+
+        assert_eq!(
+            Err(ChromaprintError::AlreadyFinished),
+            fingerprinter.feed(&[0i16; 1024])
+        );
+        assert_eq!(
+            Err(ChromaprintError::AlreadyFinished),
+            fingerprinter.finish()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_align() {
+        let a: Vec<u32> = (0..100u32)
+            .map(|idx| idx.wrapping_mul(0x9e37_79b9))
+            .collect();
+        let b = &a[10..50];
+
+        let alignment = Fingerprint(&a, Algorithm::Test2)
+            .align(&Fingerprint(b, Algorithm::Test2), None)
+            .unwrap();
+
+        assert_eq!(10, alignment.offset);
+        assert_abs_diff_eq!(1.238095, alignment.offset_seconds, epsilon = 1e-6);
+        assert_eq!(40, alignment.similarity.overlap);
+        assert_eq!(0, alignment.similarity.bit_errors);
+    }
 }
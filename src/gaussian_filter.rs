@@ -0,0 +1,136 @@
+/// Approximates a gaussian blur with standard deviation `sigma` by applying `passes` box filters.
+pub fn gaussian_filter(input: &[f64], sigma: f64, passes: usize) -> Vec<f64> {
+    let n = passes as f64;
+    let ideal_width = (12.0 * sigma * sigma / n + 1.0).sqrt().floor() as usize;
+    let lower_width = if ideal_width & 1 == 0 {
+        ideal_width - 1
+    } else {
+        ideal_width
+    };
+    let upper_width = lower_width + 2;
+
+    let wl = lower_width as f64;
+    let lower_passes =
+        ((12.0 * sigma * sigma - n * wl * wl - 4.0 * n * wl - 3.0 * n) / (-4.0 * wl - 4.0)).round();
+    let lower_passes = lower_passes.max(0.0) as usize;
+
+    let mut output = input.to_vec();
+    for pass in 0..passes {
+        let width = if pass < lower_passes {
+            lower_width
+        } else {
+            upper_width
+        };
+
+        output = box_filter(&output, width);
+    }
+
+    output
+}
+
+/// Averages each value with its neighbours in a window of `width` values. The input is mirrored
+/// at its edges.
+fn box_filter(input: &[f64], width: usize) -> Vec<f64> {
+    if input.is_empty() || width == 0 {
+        return input.to_vec();
+    }
+
+    let half_width = (width / 2) as isize;
+    let len = input.len() as isize;
+    let reflect = |idx: isize| -> f64 {
+        let mut idx = idx;
+        loop {
+            if idx < 0 {
+                idx = -idx - 1;
+            } else if idx >= len {
+                idx = 2 * len - idx - 1;
+            } else {
+                return input[idx as usize];
+            }
+        }
+    };
+
+    let mut sum: f64 = (-half_width..(width as isize - half_width))
+        .map(&reflect)
+        .sum();
+
+    let mut output = Vec::with_capacity(input.len());
+    for idx in 0..len {
+        output.push(sum / width as f64);
+        sum += reflect(idx + width as isize - half_width) - reflect(idx - half_width);
+    }
+
+    output
+}
+
+/// Computes the rate of change of `input` with central differences, falling back to one-sided
+/// differences at the edges.
+pub fn gradient(input: &[f64]) -> Vec<f64> {
+    let len = input.len();
+    (0..len)
+        .map(|idx| {
+            if len < 2 {
+                0.0
+            } else if idx == 0 {
+                input[1] - input[0]
+            } else if idx == len - 1 {
+                input[idx] - input[idx - 1]
+            } else {
+                (input[idx + 1] - input[idx - 1]) / 2.0
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{box_filter, gaussian_filter, gradient};
+
+    #[test]
+    fn test_box_filter() {
+        let output = box_filter(&[1.0, 2.0, 3.0, 4.0], 3);
+
+        assert_eq!(
+            vec![4.0 / 3.0, 2.0, 3.0, 11.0 / 3.0],
+            output
+                .iter()
+                .map(|value| (value * 3.0).round() / 3.0)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_box_filter_wider_than_input() {
+        let output = box_filter(&[1.0, 2.0], 5);
+
+        assert_abs_diff_eq!(1.6, output[0], epsilon = 1e-9);
+        assert_abs_diff_eq!(1.4, output[1], epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_gaussian_filter_keeps_constant() {
+        let output = gaussian_filter(&[5.0; 40], 8.0, 3);
+
+        for value in output {
+            assert_abs_diff_eq!(5.0, value, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_gaussian_filter_smooths_step() {
+        let mut input = vec![0.0; 50];
+        input.extend_from_slice(&[10.0; 50]);
+        let output = gaussian_filter(&input, 8.0, 3);
+
+        assert!(output[40] > 0.0);
+        assert!(output[49] < 5.0);
+        assert!(output[50] > 5.0);
+        assert!(output[60] < 10.0);
+    }
+
+    #[test]
+    fn test_gradient() {
+        assert_eq!(vec![1.0, 1.5, 2.5, 3.0], gradient(&[0.0, 1.0, 3.0, 6.0]));
+        assert_eq!(vec![0.0], gradient(&[1.0]));
+    }
+}
@@ -1,22 +1,99 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
+// `core` is only declared implicitly in `no_std` crates. Modules use it for the items std
+// re-exports, and the code generated by the pyo3 macros refers to `::core`.
+#[cfg(any(feature = "std", test))]
+extern crate core;
+
+#[cfg(feature = "std")]
 extern crate base64;
+#[cfg(not(feature = "std"))]
+extern crate libm;
+#[cfg(feature = "rustfft")]
 extern crate rustfft;
 
+#[cfg(feature = "capi")]
+extern crate libc;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "png")]
+extern crate png;
+
+#[cfg(feature = "acoustid")]
+extern crate serde;
+#[cfg(feature = "acoustid")]
+extern crate serde_json;
+#[cfg(feature = "acoustid")]
+extern crate ureq;
+
+#[cfg(feature = "decode")]
+extern crate symphonia;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+#[cfg(feature = "python")]
+extern crate numpy;
+#[cfg(feature = "python")]
+extern crate pyo3;
+
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+
+#[cfg(feature = "acoustid")]
+mod acoustid;
+mod algorithm;
 mod audio_processor;
+#[cfg(feature = "rayon")]
+mod batch;
+mod bit_reader;
 mod bit_writer;
+#[cfg(feature = "capi")]
+pub mod capi;
 mod chroma;
 mod chroma_filter;
 mod chroma_normalize;
 mod classifiers;
 mod combined_buffer;
+mod compare;
+mod decimator;
+#[cfg(feature = "decode")]
+mod decode;
+#[cfg(feature = "std")]
+mod disk_index;
+mod downmixer;
+#[cfg(feature = "std")]
 mod encode;
+mod error;
+#[cfg(feature = "std")]
+mod export;
 mod fft;
 mod filter;
 mod fingerprint_calculator;
 mod fingerprint_compressor;
+mod fingerprint_decompressor;
+#[cfg(feature = "std")]
+mod fingerprint_index;
+#[cfg(feature = "std")]
+mod fingerprint_matcher;
+#[cfg(feature = "std")]
+mod gaussian_filter;
+mod math;
+#[cfg(feature = "python")]
+mod python;
 mod quantizer;
 mod resampler;
 mod rolling_integral_image;
+mod sample;
+mod shared;
+mod silence_remover;
 mod slicer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod wav;
 
 #[cfg(test)]
 #[macro_use]
@@ -30,4 +107,29 @@ mod test_data;
 
 mod fingerprinter;
 
-pub use fingerprinter::{Fingerprint, Fingerprinter};
+#[cfg(feature = "acoustid")]
+pub use acoustid::{
+    AcoustIdClient, AcoustIdError, Artist, LookupResult, Recording, Release, ReleaseDate,
+    Submission, SubmissionState, SubmissionStatus,
+};
+pub use algorithm::Algorithm;
+#[cfg(feature = "rayon")]
+pub use batch::{AudioSource, BatchFingerprint, BatchFingerprinter, RawAudio};
+pub use compare::Similarity;
+#[cfg(feature = "decode")]
+pub use decode::{fingerprint_file, AudioDecoder};
+#[cfg(feature = "std")]
+pub use disk_index::DiskIndex;
+pub use error::ChromaprintError;
+#[cfg(feature = "std")]
+pub use export::GrayImage;
+#[cfg(feature = "std")]
+pub use fingerprint_index::{Candidate, FingerprintIndex};
+#[cfg(feature = "std")]
+pub use fingerprint_matcher::{FingerprintMatcher, Segment};
+pub use fingerprinter::{
+    Alignment, ChromaFeatures, CompressedFingerprint, Fingerprint, Fingerprinter,
+};
+pub use sample::{Sample, I24};
+#[cfg(feature = "std")]
+pub use wav::{fingerprint_wav, SampleFormat, WavReader, WavSpec};
@@ -0,0 +1,33 @@
+//! Floating point functions which aren't part of `core`. They come from std when it's available,
+//! so fingerprints don't change, and from libm otherwise.
+
+macro_rules! math {
+    ($($name:ident($ty:ty) => $method:ident;)*) => {
+        $(
+            #[cfg(feature = "std")]
+            #[inline]
+            pub fn $name(x: $ty) -> $ty {
+                x.$method()
+            }
+
+            #[cfg(not(feature = "std"))]
+            #[inline]
+            pub fn $name(x: $ty) -> $ty {
+                ::libm::$name(x)
+            }
+        )*
+    };
+}
+
+math! {
+    ceil(f64) => ceil;
+    cos(f64) => cos;
+    cosf(f32) => cos;
+    floor(f64) => floor;
+    log(f64) => ln;
+    log2(f64) => log2;
+    round(f64) => round;
+    roundf(f32) => round;
+    sin(f64) => sin;
+    sqrt(f64) => sqrt;
+}
@@ -0,0 +1,218 @@
+//! Python bindings built with pyo3.
+//!
+//! Wheels are built with maturin, which enables the `extension-module` feature of pyo3 as
+//! configured in `pyproject.toml`. The GIL is released while audio is fingerprinted, so several
+//! threads can fingerprint at once.
+
+use numpy::{PyArray1, PyReadonlyArrayDyn};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use algorithm::Algorithm;
+use error::ChromaprintError;
+use fingerprinter::{CompressedFingerprint, Fingerprint, Fingerprinter};
+use sample::Sample;
+
+impl From<ChromaprintError> for PyErr {
+    fn from(error: ChromaprintError) -> PyErr {
+        match error {
+            ChromaprintError::AlreadyFinished | ChromaprintError::AlreadyStarted => {
+                PyRuntimeError::new_err(error.to_string())
+            }
+            _ => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// Interleaved samples in any of the dtypes supported by `Sample`. Multidimensional arrays are
+/// read in row-major order, so an array of shape `(frames, channels)` works.
+#[derive(FromPyObject)]
+enum Samples<'py> {
+    I16(PyReadonlyArrayDyn<'py, i16>),
+    F32(PyReadonlyArrayDyn<'py, f32>),
+    F64(PyReadonlyArrayDyn<'py, f64>),
+    I32(PyReadonlyArrayDyn<'py, i32>),
+    U8(PyReadonlyArrayDyn<'py, u8>),
+}
+
+/// Fingerprints audio fed in chunks of numpy arrays.
+#[pyclass(name = "Fingerprinter", module = "chromaprint")]
+pub struct PyFingerprinter {
+    fingerprinter: Fingerprinter,
+}
+
+#[pymethods]
+impl PyFingerprinter {
+    /// `algorithm` is the id used in compressed fingerprints, from 0 for TEST1 to 4 for TEST5.
+    #[new]
+    #[pyo3(signature = (sample_rate, channels, algorithm = 1))]
+    fn new(sample_rate: u32, channels: u16, algorithm: u8) -> PyResult<PyFingerprinter> {
+        Ok(PyFingerprinter {
+            fingerprinter: Fingerprinter::with_algorithm(
+                sample_rate,
+                channels,
+                to_algorithm(algorithm)?,
+            )?,
+        })
+    }
+
+    /// Feeds interleaved samples from a C-contiguous array. Floating point samples are expected
+    /// to be in `[-1.0, 1.0]`.
+    fn feed(&mut self, py: Python, samples: Samples) -> PyResult<()> {
+        match samples {
+            Samples::I16(samples) => feed(py, &mut self.fingerprinter, &samples),
+            Samples::F32(samples) => feed(py, &mut self.fingerprinter, &samples),
+            Samples::F64(samples) => feed(py, &mut self.fingerprinter, &samples),
+            Samples::I32(samples) => feed(py, &mut self.fingerprinter, &samples),
+            Samples::U8(samples) => feed(py, &mut self.fingerprinter, &samples),
+        }
+    }
+
+    fn finish(&mut self, py: Python) -> PyResult<()> {
+        let fingerprinter = &mut self.fingerprinter;
+
+        Ok(py.detach(|| fingerprinter.finish())?)
+    }
+
+    /// The raw sub-fingerprints computed so far as an array of `uint32`.
+    fn fingerprint<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_slice(py, self.fingerprinter.fingerprint().0)
+    }
+
+    /// The id of the algorithm used.
+    #[getter]
+    fn algorithm(&self) -> u8 {
+        self.fingerprinter.algorithm().id()
+    }
+
+    /// The fingerprint computed so far in the compressed format.
+    fn compress<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let compressed = self.fingerprinter.fingerprint().compress()?;
+
+        Ok(PyBytes::new(py, &compressed.0))
+    }
+
+    /// The fingerprint computed so far compressed and encoded like fpcalc does.
+    fn encode(&self) -> PyResult<String> {
+        Ok(self.fingerprinter.fingerprint().compress()?.encode())
+    }
+}
+
+fn feed<S: Sample + numpy::Element + Sync>(
+    py: Python,
+    fingerprinter: &mut Fingerprinter,
+    samples: &PyReadonlyArrayDyn<S>,
+) -> PyResult<()> {
+    let samples = samples.as_slice()?;
+
+    Ok(py.detach(|| fingerprinter.feed(samples))?)
+}
+
+fn to_algorithm(id: u8) -> PyResult<Algorithm> {
+    Ok(Algorithm::from_id(id).ok_or(ChromaprintError::UnknownAlgorithm(id))?)
+}
+
+/// Compresses raw sub-fingerprints computed with the algorithm with id `algorithm`.
+#[pyfunction]
+#[pyo3(signature = (fingerprint, algorithm = 1))]
+fn compress<'py>(
+    py: Python<'py>,
+    fingerprint: Vec<u32>,
+    algorithm: u8,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let compressed = Fingerprint(&fingerprint, to_algorithm(algorithm)?).compress()?;
+
+    Ok(PyBytes::new(py, &compressed.0))
+}
+
+/// Recovers the raw sub-fingerprints of a compressed fingerprint and the id of the algorithm
+/// used to compute them.
+#[pyfunction]
+fn decompress<'py>(
+    py: Python<'py>,
+    compressed: &[u8],
+) -> PyResult<(Bound<'py, PyArray1<u32>>, u8)> {
+    let (fingerprint, algorithm) = CompressedFingerprint(compressed.to_vec()).decompress()?;
+
+    Ok((PyArray1::from_vec(py, fingerprint), algorithm.id()))
+}
+
+/// Encodes a compressed fingerprint in the URL-safe base64 used by fpcalc and AcoustID.
+#[pyfunction]
+fn encode(compressed: &[u8]) -> String {
+    CompressedFingerprint(compressed.to_vec()).encode()
+}
+
+/// Parses a fingerprint encoded by `encode` or by fpcalc into its compressed form.
+#[pyfunction]
+fn decode<'py>(py: Python<'py>, encoded: &str) -> PyResult<Bound<'py, PyBytes>> {
+    Ok(PyBytes::new(py, &CompressedFingerprint::decode(encoded)?.0))
+}
+
+/// The fraction of bits which match between two raw fingerprints when `b` starts `offset` items
+/// into `a`, or `None` when they don't overlap.
+#[pyfunction]
+#[pyo3(signature = (a, b, offset = 0))]
+fn compare(a: Vec<u32>, b: Vec<u32>, offset: isize) -> Option<f64> {
+    Fingerprint(&a, Algorithm::default())
+        .compare(&Fingerprint(&b, Algorithm::default()), offset)
+        .map(|similarity| similarity.score())
+}
+
+/// The offset at which `b` best lines up with `a`, or `None` when they share nothing. Only
+/// offsets within `max_offset` items are considered when it is given.
+#[pyfunction]
+#[pyo3(signature = (a, b, max_offset = None))]
+fn best_offset(a: Vec<u32>, b: Vec<u32>, max_offset: Option<usize>) -> Option<isize> {
+    Fingerprint(&a, Algorithm::default())
+        .align(&Fingerprint(&b, Algorithm::default()), max_offset)
+        .map(|alignment| alignment.offset)
+}
+
+#[pymodule]
+fn chromaprint(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_class::<PyFingerprinter>()?;
+    module.add_function(wrap_pyfunction!(python::compress, module)?)?;
+    module.add_function(wrap_pyfunction!(python::decompress, module)?)?;
+    module.add_function(wrap_pyfunction!(python::encode, module)?)?;
+    module.add_function(wrap_pyfunction!(python::decode, module)?)?;
+    module.add_function(wrap_pyfunction!(python::compare, module)?)?;
+    module.add_function(wrap_pyfunction!(python::best_offset, module)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, compress, decode, encode, PyFingerprinter};
+    use pyo3::exceptions::PyValueError;
+    use pyo3::types::PyBytesMethods;
+    use pyo3::Python;
+
+    #[test]
+    fn test_helpers() {
+        Python::initialize();
+        Python::attach(|py| {
+            let fingerprint = vec![1, 2, 3, 0xffff_ffff];
+            let compressed = compress(py, fingerprint.clone(), 1).unwrap();
+            let encoded = encode(compressed.as_bytes());
+
+            assert_eq!(
+                compressed.as_bytes(),
+                decode(py, &encoded).unwrap().as_bytes()
+            );
+            assert_eq!(
+                Some(1.0),
+                compare(fingerprint.clone(), fingerprint.clone(), 0)
+            );
+            assert!(compress(py, fingerprint, 5)
+                .unwrap_err()
+                .is_instance_of::<PyValueError>(py));
+            assert!(decode(py, "!!")
+                .unwrap_err()
+                .is_instance_of::<PyValueError>(py));
+            assert!(PyFingerprinter::new(0, 1, 1).is_err());
+        });
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub struct Quantizer {
     t0: f64,
     t1: f64,
@@ -1,13 +1,18 @@
-use std::f64::consts::PI;
+use core::f64::consts::PI;
+use error::ChromaprintError;
+use math;
+use shared::Shared;
 
 const FILTER_SHIFT: i32 = 15;
 
+#[derive(Clone)]
 pub struct Resampler {
     phase_shift: i32,
     phase_mask: i32,
     linear: bool,
     filter_length: i32,
-    filter_bank: Vec<i16>,
+    /// Shared between clones, as it only depends on the sample rates.
+    filter_bank: Shared<[i16]>,
     src_incr: i32,
     ideal_dst_incr: i32,
     dst_incr: i32,
@@ -18,16 +23,25 @@ pub struct Resampler {
 
 impl Resampler {
     pub fn new(
-        out_rate: i32,
-        in_rate: i32,
+        out_rate: u32,
+        in_rate: u32,
         filter_size: i32,
         phase_shift: i32,
         linear: bool,
         cutoff: f64,
-    ) -> Resampler {
+    ) -> Result<Resampler, ChromaprintError> {
+        // The phase increment is stored in an `i32`.
+        let max_rate = (i32::MAX >> phase_shift) as u32;
+        if out_rate == 0 || out_rate > max_rate {
+            return Err(ChromaprintError::InvalidSampleRate(out_rate));
+        }
+        if in_rate == 0 || in_rate > max_rate {
+            return Err(ChromaprintError::InvalidSampleRate(in_rate));
+        }
+
         let factor = ((out_rate as f64) * cutoff / (in_rate as f64)).min(1.0);
         let phase_count = 1 << phase_shift;
-        let filter_length = ((filter_size as f64 / factor).ceil() as i32).max(1);
+        let filter_length = (math::ceil(filter_size as f64 / factor) as i32).max(1);
 
         let mut filter_bank = vec![0i16; (filter_length * (phase_count + 1)) as usize];
         make_filter_bank(
@@ -45,21 +59,21 @@ impl Resampler {
         filter_bank[(filter_length * phase_count) as usize] =
             filter_bank[(filter_length - 1) as usize];
 
-        let dst_incr = in_rate * phase_count;
+        let dst_incr = in_rate as i32 * phase_count;
 
-        Resampler {
+        Ok(Resampler {
             phase_shift,
             phase_mask: phase_count - 1,
             linear,
             filter_length,
-            filter_bank,
-            src_incr: out_rate,
+            filter_bank: filter_bank.into(),
+            src_incr: out_rate as i32,
             ideal_dst_incr: dst_incr,
             dst_incr,
             index: -(phase_count as i32) * (((filter_length as i32) - 1) / 2),
             compensation_distance: 0,
             frac: 0,
-        }
+        })
     }
 
     /// Resamples the contents of `src` and writes the output to `dst`.
@@ -106,9 +120,9 @@ impl Resampler {
                 let mut val: i32 = 0;
 
                 if sample_index < 0 {
-                    for i in 0..(self.filter_length as usize) {
-                        val += (src[(sample_index + 1).abs() as usize % src.len()]
-                            * filter[filter_offset + i]) as i32;
+                    for i in 0..self.filter_length {
+                        val += (src[(sample_index + i).unsigned_abs() as usize % src.len()] as i32)
+                            * (filter[filter_offset + i as usize] as i32);
                     }
                 } else if sample_index + self.filter_length > src.len() as i32 {
                     break;
@@ -133,7 +147,7 @@ impl Resampler {
                 }
 
                 val = (val + (1 << (FILTER_SHIFT - 1))) >> FILTER_SHIFT;
-                dst[dst_index] = if i32::saturating_add(val, 32768) >= ::std::i32::MAX {
+                dst[dst_index] = if i32::saturating_add(val, 32768) >= i32::MAX {
                     (val >> 31) ^ 32767
                 } else {
                     val
@@ -208,12 +222,12 @@ fn make_filter_bank(
             if x == 0.0 {
                 y = 1.0;
             } else {
-                y = x.sin() / x;
+                y = math::sin(x) / x;
             }
 
             // Window Type 9
             w = 2.0 * x / (factor * (tap_count as f64) * PI);
-            y *= bessel(9.0 * (1.0 - w * w).max(0.0).sqrt());
+            y *= bessel(9.0 * math::sqrt((1.0 - w * w).max(0.0)));
 
             tab[i] = y;
             norm += y;
@@ -221,7 +235,7 @@ fn make_filter_bank(
 
         for i in 0..tap_count {
             filter[phase * tap_count + i] = clip(
-                (tab[i] * scale / norm).round() as i32,
+                math::round(tab[i] * scale / norm) as i32,
                 i16::min_value() as i32,
                 i16::max_value() as i32,
             ) as i16;
@@ -261,12 +275,13 @@ fn clip(a: i32, a_min: i32, a_max: i32) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::{make_filter_bank, Resampler};
+    use error::ChromaprintError;
     use std::error::Error;
     use std::path::PathBuf;
     use tests::{load_audio_file, load_stero_audio_file};
 
-    const TARGET_SAMPLE_RATE: i32 = 11025;
-    const INPUT_SAMPLE_RATE: i32 = 44100;
+    const TARGET_SAMPLE_RATE: u32 = 11025;
+    const INPUT_SAMPLE_RATE: u32 = 44100;
     const RESAMPLE_FILTER_LENGTH: i32 = 16;
     const RESAMPLE_PHASE_SHIFT: i32 = 8;
     const RESAMPLE_LINEAR: bool = false;
@@ -322,7 +337,7 @@ mod tests {
             RESAMPLE_PHASE_SHIFT,
             RESAMPLE_LINEAR,
             RESAMPLE_SAMPLE_CUTOFF,
-        );
+        )?;
         let mut output = vec![0; samples.len()];
         let (_src_consumed, last_dst_idx) = resampler.resample(&samples, &mut output);
         output.truncate(last_dst_idx + 1);
@@ -335,4 +350,57 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_resample_start() -> Result<(), Box<dyn Error>> {
+        // The first outputs are centered before the start of the input, so the filter reads the
+        // samples mirrored around the first one like FFmpeg does.
+        let mut resampler = Resampler::new(
+            TARGET_SAMPLE_RATE,
+            INPUT_SAMPLE_RATE,
+            RESAMPLE_FILTER_LENGTH,
+            RESAMPLE_PHASE_SHIFT,
+            RESAMPLE_LINEAR,
+            RESAMPLE_SAMPLE_CUTOFF,
+        )?;
+        let samples: Vec<i16> = (0..1000).map(|idx| (idx * 30) as i16).collect();
+
+        let sample_index = resampler.index >> resampler.phase_shift;
+        let filter_offset =
+            (resampler.filter_length * (resampler.index & resampler.phase_mask)) as usize;
+        assert!(sample_index < 0);
+
+        let val: i64 = (0..resampler.filter_length)
+            .map(|i| {
+                let mirrored = (sample_index + i).unsigned_abs() as usize;
+                samples[mirrored] as i64 * resampler.filter_bank[filter_offset + i as usize] as i64
+            })
+            .sum();
+        let expected = ((val + (1 << 14)) >> 15) as i16;
+
+        let mut output = vec![0; 250];
+        resampler.resample(&samples, &mut output);
+        assert_eq!(expected, output[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_sample_rate() {
+        for &in_rate in [0, 1 << 24].iter() {
+            let resampler = Resampler::new(
+                TARGET_SAMPLE_RATE,
+                in_rate,
+                RESAMPLE_FILTER_LENGTH,
+                RESAMPLE_PHASE_SHIFT,
+                RESAMPLE_LINEAR,
+                RESAMPLE_SAMPLE_CUTOFF,
+            );
+
+            assert_eq!(
+                Some(ChromaprintError::InvalidSampleRate(in_rate)),
+                resampler.err()
+            );
+        }
+    }
 }
@@ -1,4 +1,7 @@
+use alloc::vec::Vec;
+
 /// Computes rolling areas.
+#[derive(Clone)]
 pub struct RollingIntegralImage {
     rows: Vec<[f64; 12]>,
     rows_count: usize,
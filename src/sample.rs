@@ -0,0 +1,127 @@
+use alloc::borrow::Cow;
+use math;
+
+/// A PCM sample format which can be fed to a `Fingerprinter`.
+///
+/// Samples are converted to 16-bit integers before they are processed. Wider integer formats are
+/// scaled down, floating point samples are expected to be in `[-1.0, 1.0]` and are clipped to that
+/// range.
+pub trait Sample: Copy {
+    fn to_i16(self) -> i16;
+
+    /// Converts a slice of samples, only copying when needed.
+    fn to_i16_slice(samples: &[Self]) -> Cow<'_, [i16]> {
+        Cow::Owned(samples.iter().map(|sample| sample.to_i16()).collect())
+    }
+}
+
+/// A signed 24-bit sample stored in the low 24 bits of an `i32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct I24(pub i32);
+
+impl I24 {
+    /// Reads a little endian packed 24-bit sample.
+    pub fn from_le_bytes(bytes: [u8; 3]) -> I24 {
+        I24(i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8)
+    }
+}
+
+impl Sample for i16 {
+    fn to_i16(self) -> i16 {
+        self
+    }
+
+    fn to_i16_slice(samples: &[i16]) -> Cow<'_, [i16]> {
+        Cow::Borrowed(samples)
+    }
+}
+
+impl Sample for u8 {
+    fn to_i16(self) -> i16 {
+        (self as i16 - 128) << 8
+    }
+}
+
+impl Sample for I24 {
+    fn to_i16(self) -> i16 {
+        // Sign extends the low 24 bits before dropping the least significant byte.
+        ((self.0 << 8) >> 16) as i16
+    }
+}
+
+impl Sample for i32 {
+    fn to_i16(self) -> i16 {
+        (self >> 16) as i16
+    }
+}
+
+impl Sample for f32 {
+    fn to_i16(self) -> i16 {
+        (self as f64).to_i16()
+    }
+}
+
+impl Sample for f64 {
+    fn to_i16(self) -> i16 {
+        if self.is_nan() {
+            return 0;
+        }
+
+        math::round(self * 32768.0)
+            .max(i16::MIN as f64)
+            .min(i16::MAX as f64) as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sample, I24};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_i16() {
+        let samples = [0i16, i16::MIN, i16::MAX];
+
+        match Sample::to_i16_slice(&samples[..]) {
+            Cow::Borrowed(converted) => assert_eq!(&samples[..], converted),
+            Cow::Owned(_) => panic!("i16 samples shouldn't be copied"),
+        }
+    }
+
+    #[test]
+    fn test_u8() {
+        assert_eq!(i16::MIN, 0u8.to_i16());
+        assert_eq!(0, 128u8.to_i16());
+        assert_eq!(i16::MAX - 255, 255u8.to_i16());
+    }
+
+    #[test]
+    fn test_i24() {
+        assert_eq!(0, I24(0).to_i16());
+        assert_eq!(i16::MAX, I24(0x7f_ffff).to_i16());
+        assert_eq!(i16::MIN, I24(-0x80_0000).to_i16());
+        assert_eq!(-1, I24(-1).to_i16());
+        assert_eq!(i16::MIN, I24(0x80_0000).to_i16());
+        assert_eq!(I24(-2), I24::from_le_bytes([0xfe, 0xff, 0xff]));
+        assert_eq!(I24(0x12_3456), I24::from_le_bytes([0x56, 0x34, 0x12]));
+    }
+
+    #[test]
+    fn test_i32() {
+        assert_eq!(i16::MAX, i32::MAX.to_i16());
+        assert_eq!(i16::MIN, i32::MIN.to_i16());
+        assert_eq!(1, 0x1_0000i32.to_i16());
+    }
+
+    #[test]
+    fn test_float() {
+        assert_eq!(0, 0.0f32.to_i16());
+        assert_eq!(16384, 0.5f32.to_i16());
+        assert_eq!(-16384, (-0.5f64).to_i16());
+        assert_eq!(i16::MAX, 1.0f32.to_i16());
+        assert_eq!(i16::MIN, (-1.0f64).to_i16());
+        assert_eq!(i16::MAX, 3.5f32.to_i16());
+        assert_eq!(i16::MIN, (-20.0f32).to_i16());
+        assert_eq!(0, f32::NAN.to_i16());
+    }
+}
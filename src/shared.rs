@@ -0,0 +1,9 @@
+//! The pointer the precomputed tables of the pipeline are shared through, so clones of a
+//! fingerprinter don't compute them again. `Arc` needs atomic pointers, which targets such as
+//! thumbv6m and riscv32imc don't have, so `Rc` is used there instead and fingerprinters can't be
+//! sent between threads.
+
+#[cfg(not(target_has_atomic = "ptr"))]
+pub use alloc::rc::Rc as Shared;
+#[cfg(target_has_atomic = "ptr")]
+pub use alloc::sync::Arc as Shared;
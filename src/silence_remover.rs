@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+/// The number of samples averaged when looking for the end of the leading silence, about 5 ms
+/// of audio at 11025 Hz.
+pub const SILENCE_WINDOW: usize = 55;
+
+/// Strips the silence at the start of a stream of samples.
+#[derive(Clone)]
+pub struct SilenceRemover {
+    threshold: u32,
+    average: MovingAverage,
+    started: bool,
+}
+
+impl SilenceRemover {
+    /// # Arguments
+    /// * `threshold` - The average absolute amplitude above which audio is no longer considered
+    ///   silent.
+    /// * `window` - The number of samples the amplitude is averaged over.
+    pub fn new(threshold: u16, window: usize) -> SilenceRemover {
+        SilenceRemover {
+            threshold: threshold as u32,
+            average: MovingAverage::new(window),
+            started: false,
+        }
+    }
+
+    /// Returns the part of `data` which isn't leading silence.
+    pub fn process<'a>(&mut self, data: &'a [i16]) -> &'a [i16] {
+        if self.started {
+            return data;
+        }
+
+        for (idx, sample) in data.iter().enumerate() {
+            self.average.add((*sample as i32).unsigned_abs());
+            if self.average.average() > self.threshold {
+                self.started = true;
+                return &data[idx..];
+            }
+        }
+
+        &[]
+    }
+}
+
+#[derive(Clone)]
+struct MovingAverage {
+    buffer: Vec<u32>,
+    offset: usize,
+    count: usize,
+    sum: u64,
+}
+
+impl MovingAverage {
+    fn new(size: usize) -> MovingAverage {
+        MovingAverage {
+            buffer: vec![0; size.max(1)],
+            offset: 0,
+            count: 0,
+            sum: 0,
+        }
+    }
+
+    fn add(&mut self, value: u32) {
+        self.sum = self.sum + value as u64 - self.buffer[self.offset] as u64;
+        self.buffer[self.offset] = value;
+        self.offset = (self.offset + 1) % self.buffer.len();
+        self.count = usize::min(self.count + 1, self.buffer.len());
+    }
+
+    fn average(&self) -> u32 {
+        if self.count == 0 {
+            0
+        } else {
+            (self.sum / self.count as u64) as u32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MovingAverage, SilenceRemover};
+
+    #[test]
+    fn test_moving_average() {
+        let mut average = MovingAverage::new(2);
+        assert_eq!(0, average.average());
+
+        average.add(4);
+        assert_eq!(4, average.average());
+
+        average.add(8);
+        assert_eq!(6, average.average());
+
+        average.add(2);
+        assert_eq!(5, average.average());
+    }
+
+    #[test]
+    fn test_moving_average_large_window() {
+        let mut average = MovingAverage::new(200_000);
+        for _ in 0..200_000 {
+            average.add(32768);
+        }
+
+        assert_eq!(32768, average.average());
+    }
+
+    #[test]
+    fn test_remove_leading_silence() {
+        let mut remover = SilenceRemover::new(100, 2);
+
+        assert!(remover.process(&[0, 10, -10, 5]).is_empty());
+        assert_eq!(&[300, 0, 0][..], remover.process(&[300, 0, 0]));
+        assert_eq!(&[0, 0, 1][..], remover.process(&[0, 0, 1]));
+    }
+
+    #[test]
+    fn test_loud_start() {
+        let mut remover = SilenceRemover::new(100, 55);
+        let data = [i16::MIN, 0, 1];
+
+        assert_eq!(&data[..], remover.process(&data));
+    }
+}
@@ -1,6 +1,8 @@
+use alloc::vec::Vec;
 use combined_buffer::CombinedBuffer;
-use std::mem;
+use core::mem;
 
+#[derive(Clone)]
 pub struct Slicer<T> {
     slice_size: usize,
     buffer: Vec<T>,
@@ -47,6 +49,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct FixedSlicer<T> {
     slicer: Slicer<T>,
     increment: usize,
@@ -5,15 +5,15 @@ use resampler::Resampler;
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
-use std::mem;
 use std::path::Path;
 use std::path::PathBuf;
 
 const MIN_FREQ: u32 = 28;
 const MAX_FREQ: u32 = 3520;
 const FRAME_SIZE: usize = 4096;
-const TARGET_SAMPLE_RATE: i32 = 11025;
-const INPUT_SAMPLE_RATE: i32 = 44100;
+const FRAME_OVERLAP: usize = FRAME_SIZE - FRAME_SIZE / 3;
+const TARGET_SAMPLE_RATE: u32 = 11025;
+const INPUT_SAMPLE_RATE: u32 = 44100;
 const RESAMPLE_FILTER_LENGTH: i32 = 16;
 const RESAMPLE_PHASE_SHIFT: i32 = 8;
 const RESAMPLE_LINEAR: bool = false;
@@ -31,14 +31,15 @@ fn test_chromaprint() -> Result<(), Box<dyn Error>> {
         RESAMPLE_PHASE_SHIFT,
         RESAMPLE_LINEAR,
         RESAMPLE_SAMPLE_CUTOFF,
-    );
+    )?;
 
-    let mut fft = Fft::new();
+    let mut fft = Fft::new(FRAME_SIZE, FRAME_OVERLAP);
     let chroma = Chroma::new(
         MIN_FREQ,
         MAX_FREQ,
         FRAME_SIZE as u32,
-        TARGET_SAMPLE_RATE as u32,
+        TARGET_SAMPLE_RATE,
+        false,
     );
     let mut image = Vec::new();
 
@@ -126,7 +127,7 @@ fn test_chromaprint() -> Result<(), Box<dyn Error>> {
 pub fn load_stero_audio_file<T: AsRef<Path>>(path: T) -> Result<Vec<i16>, Box<dyn Error>> {
     Ok(load_audio_file(&path)?
         .chunks(2)
-        .map(|chunk| (chunk[0] + chunk[1]) / (chunk.len() as i16))
+        .map(|chunk| ((chunk[0] as i32 + chunk[1] as i32) / (chunk.len() as i32)) as i16)
         .collect())
 }
 
@@ -137,10 +138,6 @@ pub fn load_audio_file<T: AsRef<Path>>(path: T) -> Result<Vec<i16>, Box<dyn Erro
 
     Ok(buffer
         .chunks(2)
-        .map(|chunk| from_ne_bytes([chunk[0], chunk[1]]))
+        .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
         .collect())
 }
-
-pub fn from_ne_bytes(bytes: [u8; 2]) -> i16 {
-    unsafe { mem::transmute(bytes) }
-}
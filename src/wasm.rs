@@ -0,0 +1,330 @@
+//! JavaScript bindings built with wasm-bindgen.
+//!
+//! The streaming fingerprinter only passes typed arrays across the boundary, so it can run inside
+//! an `AudioWorkletProcessor` where `TextEncoder` and `TextDecoder` aren't available. The helpers
+//! taking or returning strings are meant for the main thread or workers.
+
+use wasm_bindgen::prelude::*;
+
+use algorithm::Algorithm;
+use error::ChromaprintError;
+use fingerprinter::{ChromaFeatures, CompressedFingerprint, Fingerprint, Fingerprinter};
+
+/// Fingerprints audio fed in chunks, such as the `Float32Array` blocks from Web Audio.
+#[wasm_bindgen(js_name = Fingerprinter)]
+pub struct WasmFingerprinter {
+    fingerprinter: Fingerprinter,
+
+    /// The samples given to `feedChannel` for each channel which the other channels haven't
+    /// caught up with yet.
+    planar: Vec<Vec<f32>>,
+}
+
+#[wasm_bindgen(js_class = Fingerprinter)]
+impl WasmFingerprinter {
+    /// Creates a fingerprinter for interleaved audio. `algorithm` is the id used in compressed
+    /// fingerprints, from 0 for TEST1 to 4 for TEST5, and defaults to TEST2.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        sample_rate: u32,
+        channels: u16,
+        algorithm: Option<u8>,
+    ) -> Result<WasmFingerprinter, JsError> {
+        let algorithm = match algorithm {
+            Some(id) => Algorithm::from_id(id).ok_or(ChromaprintError::UnknownAlgorithm(id))?,
+            None => Algorithm::default(),
+        };
+
+        Ok(WasmFingerprinter {
+            fingerprinter: Fingerprinter::with_algorithm(sample_rate, channels, algorithm)?,
+            planar: vec![Vec::new(); channels as usize],
+        })
+    }
+
+    /// Feeds interleaved samples in `[-1.0, 1.0]`.
+    pub fn feed(&mut self, samples: &[f32]) -> Result<(), JsError> {
+        Ok(self.fingerprinter.feed(samples)?)
+    }
+
+    /// Feeds the samples of a single channel in `[-1.0, 1.0]`, like each `Float32Array` of an
+    /// `AudioWorkletProcessor` input. Frames are fed once every channel has been given their
+    /// samples, so don't mix this with `feed`.
+    #[wasm_bindgen(js_name = feedChannel)]
+    pub fn feed_channel(&mut self, channel: u16, samples: &[f32]) -> Result<(), JsError> {
+        let channels = self.planar.len();
+        self.planar
+            .get_mut(channel as usize)
+            .ok_or_else(|| {
+                JsError::new(&format!(
+                    "channel {} is out of range for audio with {} channels",
+                    channel, channels
+                ))
+            })?
+            .extend_from_slice(samples);
+
+        let frames = self.planar.iter().map(Vec::len).min().unwrap_or(0);
+        if frames == 0 {
+            return Ok(());
+        }
+
+        let planar = &self.planar;
+        let interleaved: Vec<f32> = (0..frames)
+            .flat_map(|frame| planar.iter().map(move |samples| samples[frame]))
+            .collect();
+        for samples in &mut self.planar {
+            samples.drain(..frames);
+        }
+
+        Ok(self.fingerprinter.feed(&interleaved)?)
+    }
+
+    pub fn finish(&mut self) -> Result<(), JsError> {
+        Ok(self.fingerprinter.finish()?)
+    }
+
+    /// The raw sub-fingerprints computed so far.
+    pub fn fingerprint(&self) -> Vec<u32> {
+        self.fingerprinter.fingerprint().0.to_vec()
+    }
+
+    /// The id of the algorithm used.
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> u8 {
+        self.fingerprinter.algorithm().id()
+    }
+
+    /// The fingerprint computed so far in the compressed format.
+    pub fn compress(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.fingerprinter.fingerprint().compress()?.0)
+    }
+
+    /// Keeps the chroma features of each frame for `takeChromaFeatures`. Fails once audio has been
+    /// fed.
+    #[wasm_bindgen(js_name = recordChromaFeatures)]
+    pub fn record_chroma_features(&mut self) -> Result<(), JsError> {
+        Ok(self.fingerprinter.record_chroma_features()?)
+    }
+
+    /// Removes and returns the chroma features recorded since they were last taken, so a long
+    /// stream can be visualized as it is fed without copying every row each time.
+    #[wasm_bindgen(js_name = takeChromaFeatures)]
+    pub fn take_chroma_features(&mut self) -> WasmChromaFeatures {
+        WasmChromaFeatures {
+            rows: self.fingerprinter.take_chroma_features(),
+        }
+    }
+}
+
+/// Rows of chroma features taken from a `Fingerprinter`.
+#[wasm_bindgen(js_name = ChromaFeatures)]
+pub struct WasmChromaFeatures {
+    rows: Vec<ChromaFeatures>,
+}
+
+#[wasm_bindgen(js_class = ChromaFeatures)]
+impl WasmChromaFeatures {
+    /// The number of rows.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The start of each row in seconds from the start of the audio.
+    #[wasm_bindgen(getter)]
+    pub fn timestamps(&self) -> Vec<f64> {
+        self.rows.iter().map(|row| row.timestamp).collect()
+    }
+
+    /// The raw chroma features, 12 values per row.
+    #[wasm_bindgen(getter)]
+    pub fn raw(&self) -> Vec<f64> {
+        self.stage(|row| &row.raw)
+    }
+
+    /// The chroma features after the chroma filter, 12 values per row.
+    #[wasm_bindgen(getter)]
+    pub fn filtered(&self) -> Vec<f64> {
+        self.stage(|row| &row.filtered)
+    }
+
+    /// The normalized chroma features, 12 values per row. These are the rows the fingerprint is
+    /// computed from.
+    #[wasm_bindgen(getter)]
+    pub fn normalized(&self) -> Vec<f64> {
+        self.stage(|row| &row.normalized)
+    }
+}
+
+impl WasmChromaFeatures {
+    fn stage<F: Fn(&ChromaFeatures) -> &[f64; 12]>(&self, stage: F) -> Vec<f64> {
+        self.rows
+            .iter()
+            .flat_map(|row| stage(row).iter().cloned())
+            .collect()
+    }
+}
+
+/// Compresses raw sub-fingerprints computed with the algorithm with id `algorithm`.
+#[wasm_bindgen]
+pub fn compress(fingerprint: &[u32], algorithm: u8) -> Result<Vec<u8>, JsError> {
+    let algorithm =
+        Algorithm::from_id(algorithm).ok_or(ChromaprintError::UnknownAlgorithm(algorithm))?;
+
+    Ok(Fingerprint(fingerprint, algorithm).compress()?.0)
+}
+
+/// Recovers the raw sub-fingerprints of a compressed fingerprint. The id of the algorithm is the
+/// first byte of the compressed fingerprint.
+#[wasm_bindgen]
+pub fn decompress(compressed: &[u8]) -> Result<Vec<u32>, JsError> {
+    let (fingerprint, _) = CompressedFingerprint(compressed.to_vec()).decompress()?;
+
+    Ok(fingerprint)
+}
+
+/// Encodes a compressed fingerprint in the URL-safe base64 used by fpcalc and AcoustID.
+#[wasm_bindgen]
+pub fn encode(compressed: &[u8]) -> String {
+    CompressedFingerprint(compressed.to_vec()).encode()
+}
+
+/// Parses a fingerprint encoded by `encode` or by fpcalc into its compressed form.
+#[wasm_bindgen]
+pub fn decode(encoded: &str) -> Result<Vec<u8>, JsError> {
+    Ok(CompressedFingerprint::decode(encoded)?.0)
+}
+
+/// The fraction of bits which match between two raw fingerprints when `b` starts `offset` items
+/// into `a`, or `undefined` when they don't overlap.
+#[wasm_bindgen]
+pub fn compare(a: &[u32], b: &[u32], offset: i32) -> Option<f64> {
+    let similarity = Fingerprint(a, Algorithm::default())
+        .compare(&Fingerprint(b, Algorithm::default()), offset as isize)?;
+
+    Some(similarity.score())
+}
+
+/// The offset at which `b` best lines up with `a`, or `undefined` when they share nothing. Only
+/// offsets within `maxOffset` items are considered when it is given.
+#[wasm_bindgen(js_name = bestOffset)]
+pub fn best_offset(a: &[u32], b: &[u32], max_offset: Option<u32>) -> Option<i32> {
+    let alignment = Fingerprint(a, Algorithm::default()).align(
+        &Fingerprint(b, Algorithm::default()),
+        max_offset.map(|max_offset| max_offset as usize),
+    )?;
+
+    Some(alignment.offset as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_offset, compare, compress, decompress, WasmFingerprinter};
+    use algorithm::Algorithm;
+    use fingerprinter::Fingerprinter;
+    use std::env;
+    use std::path::PathBuf;
+    use tests;
+
+    #[test]
+    fn test_streaming() {
+        let samples: Vec<f32> = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap()
+        .iter()
+        .map(|&sample| sample as f32 / 32768.0)
+        .collect();
+
+        // Web Audio delivers 128 frames at a time.
+        let mut fingerprinter = WasmFingerprinter::new(44100, 1, None).ok().unwrap();
+        for block in samples.chunks(128) {
+            fingerprinter.feed(block).ok().unwrap();
+        }
+        fingerprinter.finish().ok().unwrap();
+
+        let fingerprint = fingerprinter.fingerprint();
+        let compressed = fingerprinter.compress().ok().unwrap();
+
+        assert_eq!(1, fingerprinter.algorithm());
+        assert_eq!(compressed, compress(&fingerprint, 1).ok().unwrap());
+        assert_eq!(fingerprint, decompress(&compressed).ok().unwrap());
+        assert_eq!(Some(1.0), compare(&fingerprint, &fingerprint, 0));
+        assert_eq!(Some(0), best_offset(&fingerprint, &fingerprint, None));
+    }
+
+    #[test]
+    fn test_feed_channel() {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap();
+        let left: Vec<f32> = samples
+            .iter()
+            .map(|&sample| sample as f32 / 32768.0)
+            .collect();
+        let right: Vec<f32> = left.iter().map(|&sample| sample / 2.0).collect();
+
+        let interleaved: Vec<f32> = left
+            .iter()
+            .zip(&right)
+            .flat_map(|(&left, &right)| vec![left, right])
+            .collect();
+        let mut expected = Fingerprinter::new(44100, 2).unwrap();
+        expected.feed(&interleaved).unwrap();
+        expected.finish().unwrap();
+
+        // The right channel lags a block behind the left one.
+        let mut fingerprinter = WasmFingerprinter::new(44100, 2, None).ok().unwrap();
+        let mut right_blocks = right.chunks(128);
+        for (idx, block) in left.chunks(128).enumerate() {
+            fingerprinter.feed_channel(0, block).ok().unwrap();
+            if idx > 0 {
+                let block = right_blocks.next().unwrap();
+                fingerprinter.feed_channel(1, block).ok().unwrap();
+            }
+        }
+        for block in right_blocks {
+            fingerprinter.feed_channel(1, block).ok().unwrap();
+        }
+        fingerprinter.finish().ok().unwrap();
+
+        assert!(!expected.fingerprint().0.is_empty());
+        assert_eq!(expected.fingerprint().0, &fingerprinter.fingerprint()[..]);
+    }
+
+    #[test]
+    fn test_take_chroma_features() {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap();
+
+        let mut fingerprinter = WasmFingerprinter::new(44100, 1, None).ok().unwrap();
+        fingerprinter.record_chroma_features().ok().unwrap();
+        fingerprinter
+            .fingerprinter
+            .feed(&samples[..(samples.len() / 2)])
+            .unwrap();
+        let first = fingerprinter.take_chroma_features();
+        fingerprinter
+            .fingerprinter
+            .feed(&samples[(samples.len() / 2)..])
+            .unwrap();
+        fingerprinter.finish().ok().unwrap();
+        let second = fingerprinter.take_chroma_features();
+
+        assert!(first.length() > 0);
+        assert_eq!(
+            fingerprinter.fingerprint().len() + 15,
+            first.length() + second.length()
+        );
+        assert_eq!(0, fingerprinter.take_chroma_features().length());
+
+        let item_duration = Algorithm::default().item_duration();
+        let timestamps = second.timestamps();
+        assert_ulps_eq!(first.length() as f64 * item_duration, timestamps[0]);
+        assert_eq!(12 * second.length(), second.raw().len());
+        assert_eq!(12 * second.length(), second.filtered().len());
+        assert_eq!(12 * second.length(), second.normalized().len());
+    }
+}
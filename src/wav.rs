@@ -0,0 +1,405 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use fingerprinter::Fingerprinter;
+use sample::{Sample, I24};
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// The number of frames read at a time by `WavReader::feed`.
+const READ_FRAMES: usize = 4096;
+
+/// How the samples of a WAV file are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Signed integers, or unsigned integers for 8-bit samples.
+    Int,
+    Float,
+}
+
+/// The format of the samples in a WAV file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WavSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub sample_format: SampleFormat,
+}
+
+impl WavSpec {
+    /// The number of bytes taken up by one sample of each channel.
+    fn frame_size(&self) -> usize {
+        self.channels as usize * (self.bits_per_sample as usize / 8)
+    }
+}
+
+/// Streams the samples of a RIFF WAV file.
+///
+/// 8, 16, 24 and 32-bit integer PCM and 32 and 64-bit IEEE float samples are supported, with the
+/// plain and `WAVE_FORMAT_EXTENSIBLE` headers.
+pub struct WavReader<R> {
+    reader: R,
+    spec: WavSpec,
+
+    /// The size of the data chunk, unless the file was written as a stream of unknown length.
+    data_size: Option<u64>,
+    remaining: u64,
+}
+
+impl<R: Read> WavReader<R> {
+    /// Reads the header of a WAV file, leaving `reader` at the first sample.
+    pub fn new(mut reader: R) -> io::Result<WavReader<R>> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(invalid_data("not a RIFF WAV file"));
+        }
+
+        let mut spec = None;
+        loop {
+            let mut chunk_header = [0u8; 8];
+            reader.read_exact(&mut chunk_header).map_err(|err| {
+                if err.kind() == io::ErrorKind::UnexpectedEof {
+                    invalid_data("WAV file has no data chunk")
+                } else {
+                    err
+                }
+            })?;
+            let size = read_u32(&chunk_header, 4);
+
+            if &chunk_header[0..4] == b"data" {
+                let spec = spec.ok_or_else(|| invalid_data("WAV file has no fmt chunk"))?;
+                let data_size = if size == 0 || size == u32::MAX {
+                    None
+                } else {
+                    Some(size as u64)
+                };
+
+                return Ok(WavReader {
+                    reader,
+                    spec,
+                    data_size,
+                    remaining: data_size.unwrap_or(u64::MAX),
+                });
+            }
+
+            // Chunks are padded to an even number of bytes.
+            let padded_size = size as u64 + (size & 1) as u64;
+            let mut chunk = Vec::new();
+            (&mut reader).take(padded_size).read_to_end(&mut chunk)?;
+            if (chunk.len() as u64) < padded_size {
+                return Err(invalid_data("truncated WAV chunk"));
+            }
+
+            if &chunk_header[0..4] == b"fmt " {
+                spec = Some(parse_format(&chunk)?);
+            }
+        }
+    }
+
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    /// The duration of the audio in seconds, when the header records the size of the data.
+    pub fn duration(&self) -> Option<f64> {
+        self.data_size.map(|data_size| {
+            (data_size / self.spec.frame_size() as u64) as f64 / self.spec.sample_rate as f64
+        })
+    }
+
+    /// Reads up to `max_frames` frames of interleaved samples, converted to 16-bit integers like
+    /// `Fingerprinter::feed` converts them.
+    ///
+    /// # Returns
+    /// An empty vector at the end of the data.
+    pub fn read_samples(&mut self, max_frames: usize) -> io::Result<Vec<i16>> {
+        let frame_size = self.spec.frame_size();
+        let size = (max_frames as u64 * frame_size as u64).min(self.remaining);
+
+        let mut bytes = Vec::with_capacity(size as usize);
+        (&mut self.reader).take(size).read_to_end(&mut bytes)?;
+        self.remaining -= bytes.len() as u64;
+
+        // A file cut off part way through a frame ends at the last whole frame.
+        let whole_size = bytes.len() - bytes.len() % frame_size;
+        let bytes = &bytes[..whole_size];
+
+        let samples = match (self.spec.sample_format, self.spec.bits_per_sample) {
+            (SampleFormat::Int, 8) => bytes.iter().map(|sample| sample.to_i16()).collect(),
+            (SampleFormat::Int, 16) => bytes
+                .chunks(2)
+                .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                .collect(),
+            (SampleFormat::Int, 24) => bytes
+                .chunks(3)
+                .map(|sample| I24::from_le_bytes([sample[0], sample[1], sample[2]]).to_i16())
+                .collect(),
+            (SampleFormat::Int, _) => bytes
+                .chunks(4)
+                .map(|sample| (read_u32(sample, 0) as i32).to_i16())
+                .collect(),
+            (SampleFormat::Float, 32) => bytes
+                .chunks(4)
+                .map(|sample| f32::from_bits(read_u32(sample, 0)).to_i16())
+                .collect(),
+            (SampleFormat::Float, _) => bytes
+                .chunks(8)
+                .map(|sample| {
+                    let mut bits = [0u8; 8];
+                    bits.copy_from_slice(sample);
+                    f64::from_bits(u64::from_le_bytes(bits)).to_i16()
+                })
+                .collect(),
+        };
+
+        Ok(samples)
+    }
+
+    /// Feeds the rest of the samples to `fingerprinter`.
+    pub fn feed(&mut self, fingerprinter: &mut Fingerprinter) -> io::Result<()> {
+        loop {
+            let samples = self.read_samples(READ_FRAMES)?;
+            if samples.is_empty() {
+                return Ok(());
+            }
+
+            fingerprinter
+                .feed(&samples)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        }
+    }
+}
+
+/// Fingerprints a WAV file with the default algorithm.
+///
+/// # Returns
+/// The finished fingerprinter, to read the fingerprint from.
+pub fn fingerprint_wav<P: AsRef<Path>>(path: P) -> io::Result<Fingerprinter> {
+    let mut reader = WavReader::new(BufReader::new(File::open(path)?))?;
+    let spec = reader.spec();
+
+    let mut fingerprinter = Fingerprinter::new(spec.sample_rate, spec.channels)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    reader.feed(&mut fingerprinter)?;
+    fingerprinter
+        .finish()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    Ok(fingerprinter)
+}
+
+fn parse_format(chunk: &[u8]) -> io::Result<WavSpec> {
+    if chunk.len() < 16 {
+        return Err(invalid_data("truncated WAV fmt chunk"));
+    }
+
+    let mut format_tag = read_u16(chunk, 0);
+    let channels = read_u16(chunk, 2);
+    let sample_rate = read_u32(chunk, 4);
+    let bits_per_sample = read_u16(chunk, 14);
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        // The format code is held in the first two bytes of the sub-format GUID.
+        if chunk.len() < 40 {
+            return Err(invalid_data("truncated WAVE_FORMAT_EXTENSIBLE fmt chunk"));
+        }
+        format_tag = read_u16(chunk, 24);
+    }
+
+    let sample_format = match (format_tag, bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8) | (WAVE_FORMAT_PCM, 16) => SampleFormat::Int,
+        (WAVE_FORMAT_PCM, 24) | (WAVE_FORMAT_PCM, 32) => SampleFormat::Int,
+        (WAVE_FORMAT_IEEE_FLOAT, 32) | (WAVE_FORMAT_IEEE_FLOAT, 64) => SampleFormat::Float,
+        _ => {
+            return Err(invalid_data(format!(
+                "unsupported WAV format {:#06x} with {} bits per sample",
+                format_tag, bits_per_sample
+            )))
+        }
+    };
+
+    if channels == 0 {
+        return Err(invalid_data("WAV file has no channels"));
+    }
+
+    Ok(WavSpec {
+        sample_rate,
+        channels,
+        bits_per_sample,
+        sample_format,
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fingerprint_wav, SampleFormat, WavReader, WavSpec};
+    use std::env;
+    use std::fs;
+    use std::io::{Cursor, ErrorKind};
+    use std::path::PathBuf;
+    use tests;
+
+    /// Builds a WAV file with an extra chunk ahead of the data, like the LIST chunks many
+    /// encoders write.
+    fn wav_file(
+        format_tag: u16,
+        channels: u16,
+        bits: u16,
+        extensible: bool,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut format = Vec::new();
+        let written_tag = if extensible { 0xfffe } else { format_tag };
+        format.extend_from_slice(&written_tag.to_le_bytes());
+        format.extend_from_slice(&channels.to_le_bytes());
+        format.extend_from_slice(&8000u32.to_le_bytes());
+        let block_align = channels * bits / 8;
+        format.extend_from_slice(&(8000 * block_align as u32).to_le_bytes());
+        format.extend_from_slice(&block_align.to_le_bytes());
+        format.extend_from_slice(&bits.to_le_bytes());
+        if extensible {
+            format.extend_from_slice(&22u16.to_le_bytes());
+            format.extend_from_slice(&bits.to_le_bytes());
+            format.extend_from_slice(&0u32.to_le_bytes());
+            format.extend_from_slice(&format_tag.to_le_bytes());
+            format.extend_from_slice(&[
+                0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+            ]);
+        }
+
+        let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+        file.extend_from_slice(b"fmt ");
+        file.extend_from_slice(&(format.len() as u32).to_le_bytes());
+        file.extend_from_slice(&format);
+        file.extend_from_slice(b"LIST\x03\0\0\0abc\0");
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(data);
+
+        file
+    }
+
+    fn read_all(file: Vec<u8>) -> (WavSpec, Vec<i16>) {
+        let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+        let mut samples = Vec::new();
+        loop {
+            let read = reader.read_samples(2).unwrap();
+            if read.is_empty() {
+                return (reader.spec(), samples);
+            }
+            samples.extend(read);
+        }
+    }
+
+    #[test]
+    fn test_integer_formats() {
+        let (spec, samples) = read_all(wav_file(1, 1, 8, false, &[0x80, 0xff, 0x00]));
+        assert_eq!(8, spec.bits_per_sample);
+        assert_eq!(vec![0, 0x7f00, -0x8000], samples);
+
+        let (spec, samples) = read_all(wav_file(1, 2, 16, false, &[0x01, 0x00, 0x00, 0x80]));
+        assert_eq!(2, spec.channels);
+        assert_eq!(vec![1, -0x8000], samples);
+
+        let (_, samples) = read_all(wav_file(
+            1,
+            1,
+            24,
+            false,
+            &[0x00, 0x34, 0x12, 0xff, 0xff, 0xff],
+        ));
+        assert_eq!(vec![0x1234, -1], samples);
+
+        let (spec, samples) = read_all(wav_file(1, 1, 32, true, &[0x00, 0x00, 0x34, 0x12]));
+        assert_eq!(SampleFormat::Int, spec.sample_format);
+        assert_eq!(vec![0x1234], samples);
+    }
+
+    #[test]
+    fn test_float_formats() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+        data.extend_from_slice(&(-2.0f32).to_le_bytes());
+        let (spec, samples) = read_all(wav_file(3, 1, 32, false, &data));
+        assert_eq!(SampleFormat::Float, spec.sample_format);
+        assert_eq!(vec![0x4000, -0x8000], samples);
+
+        let (_, samples) = read_all(wav_file(3, 1, 64, true, &0.25f64.to_le_bytes()));
+        assert_eq!(vec![0x2000], samples);
+    }
+
+    #[test]
+    fn test_duration_and_truncation() {
+        let mut file = wav_file(1, 2, 16, false, &[0u8; 32000]);
+        let reader = WavReader::new(Cursor::new(file.clone())).unwrap();
+        assert_eq!(Some(1.0), reader.duration());
+
+        // The file ends part way through the second frame.
+        file.truncate(file.len() - 32000 + 6);
+        let (_, samples) = read_all(file);
+        assert_eq!(2, samples.len());
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let error = WavReader::new(Cursor::new(b"RIFX\0\0\0\0WAVE".to_vec()))
+            .err()
+            .unwrap();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+
+        let error = WavReader::new(Cursor::new(wav_file(1, 1, 12, false, &[])))
+            .err()
+            .unwrap();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+
+        let error = WavReader::new(Cursor::new(wav_file(2, 1, 16, false, &[])))
+            .err()
+            .unwrap();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn test_fingerprint_wav() {
+        let samples = tests::load_audio_file(
+            &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("./test_data/test_stereo_44100.raw"),
+        )
+        .unwrap();
+        let data: Vec<u8> = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes().to_vec())
+            .collect();
+        let mut file = wav_file(1, 1, 16, false, &data);
+        file[24..28].copy_from_slice(&44100u32.to_le_bytes());
+
+        let path = env::temp_dir().join(format!("chromaprint-{}.wav", std::process::id()));
+        fs::write(&path, &file).unwrap();
+        let fingerprinter = fingerprint_wav(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            "AQAAC0kkRVHCJEqU4IS6Hs8FH5eh_8jP4ztOHEoYQYwAgABBhog",
+            fingerprinter.fingerprint().compress().unwrap().encode()
+        );
+    }
+}
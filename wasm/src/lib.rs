@@ -0,0 +1,6 @@
+//! Builds the JavaScript bindings of the `wasm` feature as a WebAssembly module for wasm-pack, so
+//! the `chromaprint` crate itself stays an rlib which also builds without std.
+
+extern crate chromaprint;
+
+pub use chromaprint::wasm::*;